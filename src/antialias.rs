@@ -0,0 +1,80 @@
+use crate::Color;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SampleGrid {
+    Regular,
+    Rotated,
+}
+
+impl SampleGrid {
+    pub const NAMES: &'static [&'static str] = &["regular", "rotated"];
+
+    pub fn from_name(name: &str) -> Option<SampleGrid> {
+        match name.trim() {
+            "regular" => Some(SampleGrid::Regular),
+            "rotated" => Some(SampleGrid::Rotated),
+            _ => None,
+        }
+    }
+}
+
+pub fn validate_sample_grid(grid: String) -> Result<(), String> {
+    match SampleGrid::from_name(&grid) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "Неизвестная сетка сглаживания, допустимые значения: {}",
+            SampleGrid::NAMES.join(", ")
+        )),
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Antialias {
+    pub size: u32,
+    pub grid: SampleGrid,
+    pub adaptive: bool,
+}
+
+impl Antialias {
+    pub fn offsets(self) -> Vec<(f64, f64)> {
+        let n = self.size as f64;
+        let d = |k: u32| (k as f64 + 0.5) / n - 0.5;
+        (0..self.size)
+            .flat_map(|i| (0..self.size).map(move |j| (d(i), d(j))))
+            .map(|(di, dj)| match self.grid {
+                SampleGrid::Regular => (di + 0.5, dj + 0.5),
+                SampleGrid::Rotated => (di + dj / n + 0.5, dj - di / n + 0.5),
+            })
+            .collect()
+    }
+}
+
+fn to_linear(x: u8) -> f64 {
+    let x = x as f64 / 255.0;
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(x: f64) -> u8 {
+    let x = if x <= 0.0031308 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    };
+    (x.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+pub fn blend(colors: impl Iterator<Item = Color>) -> Color {
+    let (mut sum, mut count) = ([0.0; 3], 0);
+    for Color(r, g, b) in colors {
+        sum[0] += to_linear(r);
+        sum[1] += to_linear(g);
+        sum[2] += to_linear(b);
+        count += 1;
+    }
+    let mean = |x: f64| from_linear(x / count as f64);
+    Color(mean(sum[0]), mean(sum[1]), mean(sum[2]))
+}
@@ -0,0 +1,67 @@
+use num_complex::Complex;
+use std::f64::consts::{FRAC_PI_4, TAU};
+
+use crate::real::Real;
+use crate::G_FUNC;
+
+const SEGMENTS: u32 = 64;
+const MAX_DEPTH: u32 = 24;
+const SIMPSON_PRECISION: f64 = 0.1;
+const COUNT_PRECISION: f64 = 0.1;
+
+struct Point {
+    z: Complex<f64>,
+    f: Complex<f64>,
+    log_diff: Option<Complex<f64>>,
+}
+
+impl Point {
+    fn new(z: Complex<f64>) -> Option<Point> {
+        let f = f64::f(z);
+        if f.norm_sqr() == 0.0 || !f.re.is_finite() || !f.im.is_finite() {
+            return None;
+        }
+        let log_diff = unsafe { G_FUNC }.map(|_| f64::g(z) / f);
+        Some(Point { z, f, log_diff })
+    }
+}
+
+fn arg_change(a: &Point, b: &Point, depth: u32) -> Option<f64> {
+    let m = Point::new((a.z + b.z) / 2.0)?;
+    let (d1, d2) = ((m.f / a.f).arg(), (b.f / m.f).arg());
+    let simpson = match (a.log_diff, m.log_diff, b.log_diff) {
+        (Some(ga), Some(gm), Some(gb)) => Some(((ga + gm * 4.0 + gb) * (b.z - a.z) / 6.0).im),
+        _ => None,
+    };
+
+    if d1.abs() < FRAC_PI_4
+        && d2.abs() < FRAC_PI_4
+        && simpson.is_none_or(|s| (s - d1 - d2).abs() < SIMPSON_PRECISION)
+    {
+        Some(d1 + d2)
+    } else if depth == 0 {
+        None
+    } else {
+        Some(arg_change(a, &m, depth - 1)? + arg_change(&m, b, depth - 1)?)
+    }
+}
+
+pub fn count_zeros(corners: [Complex<f64>; 4]) -> Option<i64> {
+    let mut total = 0.0;
+    for k in 0..4 {
+        let (a, b) = (corners[k], corners[(k + 1) % 4]);
+        let mut prev = Point::new(a)?;
+        for i in 1..=SEGMENTS {
+            let next = Point::new(a + (b - a) * (i as f64 / SEGMENTS as f64))?;
+            total += arg_change(&prev, &next, MAX_DEPTH)?;
+            prev = next;
+        }
+    }
+
+    let count = total / TAU;
+    if (count - count.round()).abs() < COUNT_PRECISION {
+        Some(count.round() as i64)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,219 @@
+use num_complex::Complex;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RootOrder {
+    Arg,
+    Lex,
+    Grid,
+}
+
+impl RootOrder {
+    pub const NAMES: &'static [&'static str] = &["arg", "lex", "grid"];
+
+    pub fn from_name(name: &str) -> Option<RootOrder> {
+        match name.trim() {
+            "arg" => Some(RootOrder::Arg),
+            "lex" => Some(RootOrder::Lex),
+            "grid" => Some(RootOrder::Grid),
+            _ => None,
+        }
+    }
+}
+
+pub fn validate_root_order(order: String) -> Result<(), String> {
+    match RootOrder::from_name(&order) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "Неизвестный порядок корней, допустимые значения: {}",
+            RootOrder::NAMES.join(", ")
+        )),
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Cluster {
+    pub center: Complex<f64>,
+    pub count: u64,
+    pub tag: u32,
+}
+
+impl Cluster {
+    pub fn new(center: Complex<f64>, tag: u32) -> Cluster {
+        Cluster {
+            center,
+            count: 1,
+            tag,
+        }
+    }
+}
+
+fn find(parent: &mut [usize], mut x: usize) -> usize {
+    while parent[x] != x {
+        parent[x] = parent[parent[x]];
+        x = parent[x];
+    }
+    x
+}
+
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parent, a), find(parent, b));
+    parent[a.max(b)] = a.min(b);
+}
+
+pub fn groups(points: &[Cluster], eps: f64) -> Vec<Vec<usize>> {
+    let size = eps / 2.0;
+    let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (k, p) in points.iter().enumerate() {
+        let cell = (
+            (p.center.re / size).floor() as i64,
+            (p.center.im / size).floor() as i64,
+        );
+        cells.entry(cell).or_default().push(k);
+    }
+
+    let mut parent: Vec<usize> = (0..points.len()).collect();
+    for members in cells.values() {
+        for &k in &members[1..] {
+            union(&mut parent, members[0], k);
+        }
+    }
+    for (&(x, y), members) in &cells {
+        for (dx, dy) in (-2..=2).flat_map(|dx| (-2..=2).map(move |dy| (dx, dy))) {
+            if (dx, dy) <= (0, 0) {
+                continue;
+            }
+            let Some(other) = cells.get(&(x + dx, y + dy)) else {
+                continue;
+            };
+            if find(&mut parent, members[0]) == find(&mut parent, other[0]) {
+                continue;
+            }
+            if members.iter().any(|&k| {
+                other
+                    .iter()
+                    .any(|&l| (points[k].center - points[l].center).norm() < eps)
+            }) {
+                union(&mut parent, members[0], other[0]);
+            }
+        }
+    }
+
+    let mut index = HashMap::new();
+    let mut res: Vec<Vec<usize>> = vec![];
+    for k in 0..points.len() {
+        let root = find(&mut parent, k);
+        let c = *index.entry(root).or_insert_with(|| {
+            res.push(vec![]);
+            res.len() - 1
+        });
+        res[c].push(k);
+    }
+    res
+}
+
+pub fn merge(points: &[Cluster], group: &[usize]) -> Cluster {
+    let mut res = Cluster {
+        center: Complex::new(0.0, 0.0),
+        count: 0,
+        tag: points[group[0]].tag,
+    };
+    for &k in group {
+        let p = &points[k];
+        res.center += p.center * p.count as f64;
+        res.count += p.count;
+        res.tag = res.tag.max(p.tag);
+    }
+    res.center /= res.count as f64;
+    res
+}
+
+pub fn cluster(points: &[Cluster], eps: f64) -> Vec<Cluster> {
+    let mut res: Vec<_> = groups(points, eps)
+        .iter()
+        .map(|group| merge(points, group))
+        .collect();
+
+    res.sort_by(|a, b| {
+        a.center
+            .re
+            .total_cmp(&b.center.re)
+            .then(a.center.im.total_cmp(&b.center.im))
+    });
+    res
+}
+
+pub fn cluster_by_tag(points: &[Cluster], eps: f64) -> Vec<Cluster> {
+    let mut tags: BTreeMap<u32, Vec<Cluster>> = BTreeMap::new();
+    for &p in points {
+        tags.entry(p.tag).or_default().push(p);
+    }
+    tags.values()
+        .flat_map(|points| cluster(points, eps))
+        .collect()
+}
+
+fn snap(x: f64, eps: f64) -> f64 {
+    if x.abs() < eps {
+        0.0
+    } else {
+        x
+    }
+}
+
+fn lex_key(x: Complex<f64>, eps: f64) -> (i64, i64) {
+    ((x.re / eps).round() as i64, (x.im / eps).round() as i64)
+}
+
+pub fn sort_lex(v: &mut [Cluster], eps: f64) {
+    v.sort_by_key(|c| lex_key(c.center, eps));
+}
+
+pub fn sort_arg(v: &mut [Cluster], eps: f64) {
+    let centroid = v.iter().map(|c| c.center).sum::<Complex<f64>>() / v.len().max(1) as f64;
+    v.sort_by(|a, b| {
+        let (a, b) = (a.center - centroid, b.center - centroid);
+        let arg = |x: Complex<f64>| snap(x.im, eps).atan2(snap(x.re, eps));
+        arg(a)
+            .total_cmp(&arg(b))
+            .then(a.norm().total_cmp(&b.norm()))
+    });
+}
+
+pub fn sort_by_rank(v: &mut [Cluster], rank: impl Fn(&Cluster) -> Option<usize>, eps: f64) {
+    v.sort_by_cached_key(|c| (rank(c).unwrap_or(usize::MAX), lex_key(c.center, eps)));
+}
+
+pub struct RootIndex {
+    roots: Vec<(Complex<f64>, f64)>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    size: f64,
+}
+
+impl RootIndex {
+    pub fn new(roots: &[Cluster], tolerance: impl Fn(u32) -> f64) -> RootIndex {
+        let roots: Vec<_> = roots.iter().map(|x| (x.center, tolerance(x.tag))).collect();
+        let size = roots.iter().map(|x| x.1).fold(f64::MIN_POSITIVE, f64::max);
+        let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (k, &(center, _)) in roots.iter().enumerate() {
+            cells.entry(cell(center, size)).or_default().push(k);
+        }
+        RootIndex { roots, cells, size }
+    }
+
+    pub fn nearest(&self, z: Complex<f64>) -> Option<usize> {
+        let (x, y) = cell(z, self.size);
+        (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| (x + dx, y + dy)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .map(|&k| (k, (self.roots[k].0 - z).norm()))
+            .filter(|&(k, dist)| dist < self.roots[k].1)
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+            .map(|(k, _)| k)
+    }
+}
+
+fn cell(z: Complex<f64>, size: f64) -> (i64, i64) {
+    ((z.re / size).floor() as i64, (z.im / size).floor() as i64)
+}
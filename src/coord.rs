@@ -1,53 +1,392 @@
+use num_complex::Complex;
+use num_traits::Zero;
+use std::cmp::max;
+
+use crate::dd::DD;
+use crate::func::parse_func;
+use crate::projection::*;
+use crate::real::Real;
+
+const EXTENDED_THRESHOLD: f64 = 1e3;
+const AUTO_MIN_SIZE: f64 = 2.0;
+
+type Corner = (DD, DD);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Fit {
+    Stretch,
+    Contain,
+    Cover,
+}
+
+impl Fit {
+    pub const NAMES: &'static [&'static str] = &["stretch", "contain", "cover"];
+
+    pub fn from_name(name: &str) -> Option<Fit> {
+        match name.trim() {
+            "stretch" => Some(Fit::Stretch),
+            "contain" => Some(Fit::Contain),
+            "cover" => Some(Fit::Cover),
+            _ => None,
+        }
+    }
+}
+
+pub fn validate_fit(fit: String) -> Result<(), String> {
+    match Fit::from_name(&fit) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "Неизвестный способ вписывания, допустимые значения: {}",
+            Fit::NAMES.join(", ")
+        )),
+    }
+}
+
+pub struct View {
+    pub width: u32,
+    pub height: u32,
+    origin: (DD, DD),
+    ex: (DD, DD),
+    ey: (DD, DD),
+    projection: Projection,
+    mobius: Option<Mobius>,
+}
+
+impl View {
+    pub fn new(start: (DD, DD), end: (DD, DD), height: u32) -> View {
+        let (w, h) = (end.0 - start.0, end.1 - start.1);
+        let width = max(((w / h).to_f64() * height as f64) as u32, 1);
+        View {
+            width,
+            height,
+            origin: (start.0, end.1),
+            ex: (w / DD::from(width as f64), DD::zero()),
+            ey: (DD::zero(), -h / DD::from(height as f64)),
+            projection: Projection::Plane,
+            mobius: None,
+        }
+    }
+
+    fn with_center(center: (DD, DD), ex: (DD, DD), ey: (DD, DD), width: u32, height: u32) -> View {
+        let (half_w, half_h) = (DD::from(width as f64 / 2.0), DD::from(height as f64 / 2.0));
+        View {
+            width,
+            height,
+            origin: (
+                center.0 - ex.0 * half_w - ey.0 * half_h,
+                center.1 - ex.1 * half_w - ey.1 * half_h,
+            ),
+            ex,
+            ey,
+            projection: Projection::Plane,
+            mobius: None,
+        }
+    }
+
+    pub fn centered(center: (DD, DD), zoom: DD, angle: f64, aspect: f64, height: u32) -> View {
+        let width = max((aspect * height as f64).round() as u32, 1);
+        let step = DD::from(2.0) / zoom / DD::from(height as f64);
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let (ex, ey) = (
+            (step * DD::from(cos), step * DD::from(sin)),
+            (step * DD::from(sin), -step * DD::from(cos)),
+        );
+        View::with_center(center, ex, ey, width, height)
+    }
+
+    pub fn fit(self, width: u32, fit: Fit) -> View {
+        let norm = |v: (DD, DD)| v.0.to_f64().hypot(v.1.to_f64());
+        let (sx, sy) = (
+            norm(self.ex) * self.width as f64 / width as f64,
+            norm(self.ey),
+        );
+        let (sx, sy) = match fit {
+            Fit::Stretch => (sx, sy),
+            Fit::Contain => (sx.max(sy), sx.max(sy)),
+            Fit::Cover => (sx.min(sy), sx.min(sy)),
+        };
+        let scale = |v: (DD, DD), s: f64| {
+            let k = DD::from(s / norm(v));
+            (v.0 * k, v.1 * k)
+        };
+        let (ex, ey) = (scale(self.ex, sx), scale(self.ey, sy));
+        View {
+            projection: self.projection,
+            mobius: self.mobius,
+            ..View::with_center(self.center(), ex, ey, width, self.height)
+        }
+    }
+
+    pub fn legacy(self) -> View {
+        let ey = (-self.ey.0, -self.ey.1);
+        View {
+            projection: self.projection,
+            mobius: self.mobius,
+            ..View::with_center(self.center(), self.ex, ey, self.width, self.height)
+        }
+    }
+
+    pub fn project(self, projection: Projection, mobius: Option<Mobius>) -> View {
+        View {
+            projection,
+            mobius,
+            ..self
+        }
+    }
+
+    pub fn is_linear(&self) -> bool {
+        self.projection == Projection::Plane && self.mobius.is_none()
+    }
+
+    fn at(&self, i: f64, j: f64) -> (DD, DD) {
+        let (i, j) = (DD::from(i), DD::from(j));
+        (
+            self.origin.0 + self.ex.0 * j + self.ey.0 * i,
+            self.origin.1 + self.ex.1 * j + self.ey.1 * i,
+        )
+    }
+
+    pub fn point<T: Real>(&self, i: u32, j: u32) -> Option<Complex<T>> {
+        self.sample(i, j, (0.5, 0.5))
+    }
+
+    pub fn sample<T: Real>(&self, i: u32, j: u32, (di, dj): (f64, f64)) -> Option<Complex<T>> {
+        let (re, im) = self.at(i as f64 + di, j as f64 + dj);
+        if self.is_linear() {
+            return Some(Complex::new(T::from_dd(re), T::from_dd(im)));
+        }
+        let z = self
+            .projection
+            .apply(Complex::new(re.to_f64(), im.to_f64()))?;
+        let z = self.mobius.map_or(z, |mobius| mobius.apply(z));
+        Some(Complex::new(T::from_f64(z.re), T::from_f64(z.im)))
+    }
+
+    fn center(&self) -> (DD, DD) {
+        self.at(self.height as f64 / 2.0, self.width as f64 / 2.0)
+    }
+
+    pub fn corners(&self) -> [Complex<f64>; 4] {
+        let corner = |i: u32, j: u32| {
+            let (re, im) = self.at(i as f64, j as f64);
+            Complex::new(re.to_f64(), im.to_f64())
+        };
+        let (w, h) = (self.width, self.height);
+        let det = self.ex.0 * self.ey.1 - self.ex.1 * self.ey.0;
+        if det > DD::zero() {
+            [corner(0, 0), corner(0, w), corner(h, w), corner(h, 0)]
+        } else {
+            [corner(0, 0), corner(h, 0), corner(h, w), corner(0, w)]
+        }
+    }
+
+    pub fn contains(&self, z: Complex<f64>) -> bool {
+        let (ex, ey) = (
+            Complex::new(self.ex.0.to_f64(), self.ex.1.to_f64()),
+            Complex::new(self.ey.0.to_f64(), self.ey.1.to_f64()),
+        );
+        let d = z - Complex::new(self.origin.0.to_f64(), self.origin.1.to_f64());
+        let det = ex.re * ey.im - ex.im * ey.re;
+        let j = (d.re * ey.im - d.im * ey.re) / det;
+        let i = (ex.re * d.im - ex.im * d.re) / det;
+        (0.0..=self.width as f64).contains(&j) && (0.0..=self.height as f64).contains(&i)
+    }
+
+    pub fn around(
+        min: Complex<f64>,
+        max: Complex<f64>,
+        margin: f64,
+        aspect: f64,
+        height: u32,
+    ) -> View {
+        let center = (min + max) / 2.0;
+        let (mut w, mut h) = (
+            (max.re - min.re) * (1.0 + 2.0 * margin),
+            (max.im - min.im) * (1.0 + 2.0 * margin),
+        );
+        if w.max(h) == 0.0 {
+            (w, h) = (AUTO_MIN_SIZE, AUTO_MIN_SIZE);
+        }
+        if w < h * aspect {
+            w = h * aspect;
+        } else {
+            h = w / aspect;
+        }
+        View::new(
+            (DD::from(center.re - w / 2.0), DD::from(center.im - h / 2.0)),
+            (DD::from(center.re + w / 2.0), DD::from(center.im + h / 2.0)),
+            height,
+        )
+    }
+
+    pub fn grid(&self, scale: f64, size: u32) -> View {
+        let two = DD::from(2.0);
+        let center = self.center();
+        let half = DD::from(self.span() * scale) / two;
+        View::new(
+            (center.0 - half, center.1 - half),
+            (center.0 + half, center.1 + half),
+            size,
+        )
+        .project(self.projection, self.mobius)
+    }
+
+    pub fn span(&self) -> f64 {
+        let norm = |v: (DD, DD)| v.0.to_f64().hypot(v.1.to_f64());
+        (norm(self.ex) * self.width as f64).max(norm(self.ey) * self.height as f64)
+    }
+
+    pub fn needs_extended(&self) -> bool {
+        if !self.is_linear() {
+            return false;
+        }
+        let scale = self
+            .corners()
+            .iter()
+            .map(|x| x.re.abs().max(x.im.abs()))
+            .fold(f64::MIN_POSITIVE, f64::max);
+        let norm = |v: (DD, DD)| v.0.to_f64().hypot(v.1.to_f64());
+        let step = norm(self.ex).min(norm(self.ey));
+        step < scale * f64::EPSILON * EXTENDED_THRESHOLD
+    }
+}
+
+pub fn validate_aspect(aspect: String) -> Result<(), String> {
+    match parse_aspect(&aspect) {
+        Some(_) => Ok(()),
+        None => {
+            Err("Соотношение сторон должно иметь вид W:H или быть положительным числом".to_string())
+        }
+    }
+}
+
+pub fn parse_aspect(aspect: &str) -> Option<f64> {
+    let aspect = match aspect.split_once(':') {
+        Some((w, h)) => w.trim().parse::<f64>().ok()? / h.trim().parse::<f64>().ok()?,
+        None => aspect.trim().parse().ok()?,
+    };
+    if aspect > 0.0 && aspect.is_finite() {
+        Some(aspect)
+    } else {
+        None
+    }
+}
+
+fn parse_center(center: &str) -> Option<(DD, DD)> {
+    let (re, im) = center.split_once(',')?;
+    Some((re.trim().parse().ok()?, im.trim().parse().ok()?))
+}
+
+pub fn validate_center(center: String) -> Result<(), String> {
+    match parse_center(&center) {
+        Some(_) => Ok(()),
+        None => Err("Центр должен иметь вид Re, Im".to_string()),
+    }
+}
+
+pub fn validate_zoom(zoom: String) -> Result<(), String> {
+    match zoom.trim().parse::<DD>() {
+        Ok(x) if x > DD::zero() => Ok(()),
+        _ => Err("Увеличение должно быть положительным числом".to_string()),
+    }
+}
+
+pub fn get_centered(matches: &clap::ArgMatches, aspect: f64, height: u32) -> Option<View> {
+    let center = parse_center(matches.value_of("center")?).unwrap();
+    let zoom = matches
+        .value_of("zoom")
+        .map_or(DD::from(1.0), |x| x.trim().parse().unwrap());
+    let angle = matches
+        .value_of("rotate")
+        .map_or(0.0, |x| x.trim().parse().unwrap());
+    Some(View::centered(center, zoom, angle, aspect, height))
+}
+
+fn parse_complex(name: &str, x: &str) -> Result<Complex<f64>, String> {
+    let f = parse_func(x.trim()).map_err(|e| {
+        format!(
+            "Координата {}: неправильное выражение \"{}\"\n{}",
+            name,
+            x.trim(),
+            e
+        )
+    })?;
+    let z = f.constant().ok_or_else(|| {
+        format!(
+            "Координата {} должна быть постоянной: \"{}\"",
+            name,
+            x.trim()
+        )
+    })?;
+    if z.re.is_finite() && z.im.is_finite() {
+        Ok(z)
+    } else {
+        Err(format!(
+            "Координата {} должна быть конечной: \"{}\"",
+            name,
+            x.trim()
+        ))
+    }
+}
+
+fn parse_real(name: &str, x: &str) -> Result<DD, String> {
+    if let Ok(x) = x.trim().parse::<DD>() {
+        return Ok(x);
+    }
+    let z = parse_complex(name, x)?;
+    if z.im == 0.0 {
+        Ok(DD::from(z.re))
+    } else {
+        Err(format!(
+            "Координата {} должна быть вещественной: \"{}\"",
+            name,
+            x.trim()
+        ))
+    }
+}
+
+fn parse_coord(coord: &str) -> Result<(Corner, Corner), String> {
+    let (start, end) = match coord.split_once("..") {
+        Some((a, b)) => {
+            let (a, b) = (parse_complex("Z1", a)?, parse_complex("Z2", b)?);
+            (
+                (DD::from(a.re), DD::from(a.im)),
+                (DD::from(b.re), DD::from(b.im)),
+            )
+        }
+        None => {
+            let points: Vec<Vec<_>> = coord.split(';').map(|a| a.split(',').collect()).collect();
+            let [a, b] = &points[..] else {
+                return Err(
+                    "Координаты должны иметь вид Re1, Im1; Re2, Im2 или Z1 .. Z2".to_string(),
+                );
+            };
+            let ([x1, y1], [x2, y2]) = (&a[..], &b[..]) else {
+                return Err("Каждая точка должна иметь вид Re, Im".to_string());
+            };
+            (
+                (parse_real("Re1", x1)?, parse_real("Im1", y1)?),
+                (parse_real("Re2", x2)?, parse_real("Im2", y2)?),
+            )
+        }
+    };
+    if start.0 >= end.0 || start.1 >= end.1 {
+        Err("Конечные координаты должны быть больше начальных".to_string())
+    } else {
+        Ok((start, end))
+    }
+}
+
 pub fn validate_coord(coord: String) -> Result<(), String> {
-    let mut coord_pair = coord.split(';');
-    match (coord_pair.next(), coord_pair.next(), coord_pair.next()) {
-        (Some(a), Some(b), None) => {
-            let (mut coorda, mut coordb) = (a.split(','), b.split(','));
-            match (
-                coorda.next(),
-                coorda.next(),
-                coorda.next(),
-                coordb.next(),
-                coordb.next(),
-                coordb.next(),
-            ) {
-                (Some(x1), Some(y1), None, Some(x2), Some(y2), None) => {
-                    match (
-                        x1.trim().parse::<f64>(),
-                        y1.trim().parse::<f64>(),
-                        x2.trim().parse::<f64>(),
-                        y2.trim().parse::<f64>(),
-                    ) {
-                        (Ok(x1), Ok(y1), Ok(x2), Ok(y2)) => {
-                            if x1 >= x2 || y1 >= y2 {
-                                Err("Конечные координаты должны быть больше начальных".to_string())
-                            } else {
-                                Ok(())
-                            }
-                        }
-                        _ => Err("Координаты должны быть числами".to_string()),
-                    }
-                }
-                _ => Err("Неправильный формат координат".to_string()),
-            }
-        }
-        _ => Err("Неправильный формат координат".to_string()),
-    }
-}
-
-pub fn get_coord(matches: &clap::ArgMatches) -> ((f64, f64), (f64, f64)) {
+    parse_coord(&coord).map(|_| ())
+}
+
+pub fn get_coord(matches: &clap::ArgMatches) -> ((DD, DD), (DD, DD)) {
     matches
         .value_of("coord")
-        .map(|v| {
-            let x = v
-                .split(';')
-                .map(|a| {
-                    a.split(',')
-                        .map(|c| c.trim().parse::<f64>().unwrap())
-                        .collect::<Vec<_>>()
-                })
-                .collect::<Vec<_>>();
-            ((x[0][0], x[0][1]), (x[1][0], x[1][1]))
-        })
-        .unwrap_or(((-1.0, -1.0), (1.0, 1.0)))
+        .map(|v| parse_coord(v).unwrap())
+        .unwrap_or((
+            (DD::from(-1.0), DD::from(-1.0)),
+            (DD::from(1.0), DD::from(1.0)),
+        ))
 }
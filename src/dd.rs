@@ -0,0 +1,346 @@
+use num_traits::{Num, One, Zero};
+use std::cmp::Ordering;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+use std::str::FromStr;
+
+const SPLITTER: f64 = 134217729.0;
+const MAX_DIGITS: u32 = 40;
+const MAX_EXP: i64 = 800;
+const EXP_STEP: i64 = 256;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DD {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    (s, (a - (s - bb)) + (b - bb))
+}
+
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    (s, b - (s - a))
+}
+
+fn split(a: f64) -> (f64, f64) {
+    let t = SPLITTER * a;
+    let hi = t - (t - a);
+    (hi, a - hi)
+}
+
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    let (ah, al) = split(a);
+    let (bh, bl) = split(b);
+    (p, ((ah * bh - p) + ah * bl + al * bh) + al * bl)
+}
+
+impl DD {
+    pub fn new(hi: f64, lo: f64) -> DD {
+        let (hi, lo) = quick_two_sum(hi, lo);
+        DD { hi, lo }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    pub fn abs(self) -> DD {
+        if self.hi < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    pub fn sqrt(self) -> DD {
+        if self.hi <= 0.0 {
+            return DD::from(self.hi.sqrt());
+        }
+        let y = self.hi.sqrt();
+        let (p, e) = two_prod(y, y);
+        let r = self - DD { hi: p, lo: e };
+        DD::new(y, r.hi / (2.0 * y))
+    }
+
+    pub fn trunc(self) -> DD {
+        let hi = self.hi.trunc();
+        if hi == self.hi {
+            DD::new(hi, self.lo.trunc())
+        } else {
+            DD::from(hi)
+        }
+    }
+
+    pub fn powu(self, mut n: u32) -> DD {
+        let (mut base, mut res) = (self, DD::one());
+        while n > 0 {
+            if n & 1 == 1 {
+                res = res * base;
+            }
+            base = base * base;
+            n >>= 1;
+        }
+        res
+    }
+}
+
+impl From<f64> for DD {
+    fn from(x: f64) -> DD {
+        DD { hi: x, lo: 0.0 }
+    }
+}
+
+impl Neg for DD {
+    type Output = DD;
+
+    fn neg(self) -> DD {
+        DD {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Add for DD {
+    type Output = DD;
+
+    fn add(self, other: DD) -> DD {
+        let (s1, s2) = two_sum(self.hi, other.hi);
+        let (t1, t2) = two_sum(self.lo, other.lo);
+        let (s1, s2) = quick_two_sum(s1, s2 + t1);
+        DD::new(s1, s2 + t2)
+    }
+}
+
+impl Sub for DD {
+    type Output = DD;
+
+    fn sub(self, other: DD) -> DD {
+        self + -other
+    }
+}
+
+impl Mul for DD {
+    type Output = DD;
+
+    fn mul(self, other: DD) -> DD {
+        let (p1, p2) = two_prod(self.hi, other.hi);
+        DD::new(p1, p2 + (self.hi * other.lo + self.lo * other.hi))
+    }
+}
+
+impl Div for DD {
+    type Output = DD;
+
+    fn div(self, other: DD) -> DD {
+        let q1 = self.hi / other.hi;
+        let r = self - other * DD::from(q1);
+        let q2 = r.hi / other.hi;
+        let r = r - other * DD::from(q2);
+        let q3 = r.hi / other.hi;
+        let (q1, q2) = quick_two_sum(q1, q2);
+        DD { hi: q1, lo: q2 } + DD::from(q3)
+    }
+}
+
+impl Rem for DD {
+    type Output = DD;
+
+    fn rem(self, other: DD) -> DD {
+        self - other * (self / other).trunc()
+    }
+}
+
+impl PartialOrd for DD {
+    fn partial_cmp(&self, other: &DD) -> Option<Ordering> {
+        match self.hi.partial_cmp(&other.hi) {
+            Some(Ordering::Equal) => self.lo.partial_cmp(&other.lo),
+            ord => ord,
+        }
+    }
+}
+
+impl Zero for DD {
+    fn zero() -> DD {
+        DD::from(0.0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.hi == 0.0
+    }
+}
+
+impl One for DD {
+    fn one() -> DD {
+        DD::from(1.0)
+    }
+}
+
+impl Num for DD {
+    type FromStrRadixErr = ();
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<DD, ()> {
+        if radix == 10 {
+            s.parse()
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl FromStr for DD {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<DD, ()> {
+        let s = s.trim();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (mantissa, exp) = match s.find(['e', 'E']) {
+            Some(i) => (&s[..i], s[i + 1..].parse::<i32>().map_err(|_| ())?),
+            None => (s, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int.is_empty() && frac.is_empty() {
+            return Err(());
+        }
+
+        let mut x = DD::zero();
+        let mut exp = exp as i64 - frac.len() as i64;
+        let mut digits = 0;
+        for c in int.chars().chain(frac.chars()) {
+            let d = c.to_digit(10).ok_or(())?;
+            if digits >= MAX_DIGITS {
+                exp += 1;
+                continue;
+            }
+            if d != 0 || digits > 0 {
+                digits += 1;
+            }
+            x = x * DD::from(10.0) + DD::from(d as f64);
+        }
+
+        if x.is_zero() || exp < -MAX_EXP {
+            return Ok(if negative { -DD::zero() } else { DD::zero() });
+        }
+        if exp > MAX_EXP {
+            return Err(());
+        }
+        while exp != 0 {
+            let step = exp.clamp(-EXP_STEP, EXP_STEP);
+            let scale = DD::from(10.0).powu(step.unsigned_abs() as u32);
+            x = if step < 0 { x / scale } else { x * scale };
+            exp -= step;
+        }
+        if !x.hi.is_finite() || !x.lo.is_finite() {
+            return Err(());
+        }
+
+        Ok(if negative { -x } else { x })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> DD {
+        s.parse().unwrap()
+    }
+
+    fn close(a: DD, b: DD, eps: f64) -> bool {
+        (a - b).abs().to_f64() <= eps * b.abs().to_f64().max(f64::MIN_POSITIVE)
+    }
+
+    #[test]
+    fn parses_plain_numbers() {
+        assert_eq!(parse("1"), DD::from(1.0));
+        assert_eq!(parse("-2.5"), DD::from(-2.5));
+        assert_eq!(parse("+.5"), DD::from(0.5));
+        assert_eq!(parse("3."), DD::from(3.0));
+        assert_eq!(parse(" 1e3 "), DD::from(1000.0));
+        assert_eq!(parse("25E-1"), DD::from(2.5));
+    }
+
+    #[test]
+    fn parses_beyond_double_precision() {
+        let x = parse("0.1");
+        assert!(close(x * DD::from(10.0), DD::one(), 1e-31));
+        assert!((x - DD::from(0.1)).to_f64() != 0.0);
+
+        let y = parse("1.0000000000000000000000000001");
+        assert!(close(y - DD::one(), DD::from(1e-28), 1e-3));
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        for s in ["", "-", ".", "e5", "1e", "1.2.3", "abc", "1x", "--1"] {
+            assert!(s.parse::<DD>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        for s in ["1e309", "-1e400", "1e1000000", "99999e305"] {
+            assert!(s.parse::<DD>().is_err(), "{}", s);
+        }
+        assert!(close(parse("1e308"), DD::from(1e308), 1e-15));
+    }
+
+    #[test]
+    fn underflows_to_zero() {
+        assert_eq!(parse("1e-400").to_f64(), 0.0);
+        assert_eq!(parse("-1e-1000000").to_f64(), 0.0);
+        assert!(close(parse("1e-300"), DD::from(1e-300), 1e-15));
+    }
+
+    #[test]
+    fn parses_long_digit_strings() {
+        let x = parse(&"1".repeat(300));
+        assert!(x.hi.is_finite() && x.lo.is_finite());
+        assert!(close(x, DD::from(1e300) / DD::from(9.0), 1e-15));
+
+        let small = format!("0.{}1", "0".repeat(400));
+        assert_eq!(parse(&small).to_f64(), 0.0);
+        assert!(format!("1{}", "0".repeat(400)).parse::<DD>().is_err());
+    }
+
+    #[test]
+    fn adds_without_rounding() {
+        let x = DD::one() + DD::from(1e-20);
+        assert_eq!(x.hi, 1.0);
+        assert_eq!((x - DD::one()).to_f64(), 1e-20);
+    }
+
+    #[test]
+    fn multiplies_and_divides() {
+        let third = DD::one() / DD::from(3.0);
+        assert!(close(third * DD::from(3.0), DD::one(), 1e-31));
+        let x = DD::from(1.0 + f64::EPSILON);
+        let sq = x * x;
+        assert_eq!(sq.hi, 1.0 + 2.0 * f64::EPSILON);
+        assert_eq!(sq.lo, f64::EPSILON * f64::EPSILON);
+    }
+
+    #[test]
+    fn computes_sqrt_and_powers() {
+        let r = DD::from(2.0).sqrt();
+        assert!(close(r * r, DD::from(2.0), 1e-31));
+        assert_eq!(DD::from(2.0).powu(10), DD::from(1024.0));
+        assert_eq!(DD::from(5.0).powu(0), DD::one());
+    }
+
+    #[test]
+    fn compares_and_truncates() {
+        let a = DD::one() + DD::from(1e-20);
+        assert!(a > DD::one());
+        assert!(-a < -DD::one());
+        assert_eq!(DD::from(2.75).trunc(), DD::from(2.0));
+        assert!(close(DD::from(7.5) % DD::from(2.0), DD::from(1.5), 1e-31));
+    }
+}
@@ -1,7 +1,10 @@
+use num_complex::Complex;
 use pest::iterators::Pairs;
 use pest::pratt_parser::{Assoc, Op, PrattParser};
 use pest::Parser;
 
+const MAX_DEGREE: usize = 1000;
+
 #[derive(Parser)]
 #[grammar = "func.pest"]
 struct FuncParser;
@@ -37,27 +40,139 @@ double complex {}(double complex x) {{
 
 "#,
             fun_name,
-            self.gen_inner()
+            self.gen_inner("")
+        )
+    }
+
+    pub fn genc_float(&self, fun_name: &str) -> String {
+        format!(
+            r#"
+#include <complex.h>
+
+float complex {}(float complex x) {{
+    return {};
+}}
+
+"#,
+            fun_name,
+            self.gen_inner("f")
         )
     }
 
-    fn gen_inner(&self) -> String {
+    pub fn genc_quad(&self, fun_name: &str) -> String {
+        format!(
+            r#"
+#include <complex.h>
+#include <quadmath.h>
+
+void {}(const double *z, double *r) {{
+    __complex128 x;
+    __real__ x = (__float128)z[0] + z[1];
+    __imag__ x = (__float128)z[2] + z[3];
+    __complex128 y = {};
+    __float128 re = __real__ y, im = __imag__ y;
+    r[0] = (double)re;
+    r[1] = (double)(re - r[0]);
+    r[2] = (double)im;
+    r[3] = (double)(im - r[2]);
+}}
+
+"#,
+            fun_name,
+            self.gen_inner("q")
+        )
+    }
+
+    fn gen_inner(&self, suffix: &str) -> String {
         match self {
             Func::Arg => "x".to_owned(),
+            Func::Num(n) if suffix == "f" => format!("{:?}f", *n as f32),
             Func::Num(n) => n.to_string(),
             Func::Im => "I".to_owned(),
-            Func::Add(a, b) => format!("({}+{})", a.gen_inner(), b.gen_inner()),
-            Func::Sub(a, b) => format!("({}-{})", a.gen_inner(), b.gen_inner()),
-            Func::Mul(a, b) => format!("({}*{})", a.gen_inner(), b.gen_inner()),
-            Func::Div(a, b) => format!("({}/{})", a.gen_inner(), b.gen_inner()),
-            Func::PowI(a, n) => format!("cpow({}, {})", a.gen_inner(), n),
-            Func::PowC(a, n) => format!("cpow({}, {})", a.gen_inner(), n),
-            Func::Sqrt(a) => format!("csqrt({})", a.gen_inner()),
-            Func::Exp(a) => format!("cexp({})", a.gen_inner()),
-            Func::Ln(a) => format!("clog({})", a.gen_inner()),
-            Func::Sin(a) => format!("csin({})", a.gen_inner()),
-            Func::Cos(a) => format!("ccos({})", a.gen_inner()),
-            Func::Tan(a) => format!("ctan({})", a.gen_inner()),
+            Func::Add(a, b) => format!("({}+{})", a.gen_inner(suffix), b.gen_inner(suffix)),
+            Func::Sub(a, b) => format!("({}-{})", a.gen_inner(suffix), b.gen_inner(suffix)),
+            Func::Mul(a, b) => format!("({}*{})", a.gen_inner(suffix), b.gen_inner(suffix)),
+            Func::Div(a, b) => format!("({}/{})", a.gen_inner(suffix), b.gen_inner(suffix)),
+            Func::PowI(a, n) => format!("cpow{}({}, {})", suffix, a.gen_inner(suffix), n),
+            Func::PowC(a, n) => format!("cpow{}({}, {})", suffix, a.gen_inner(suffix), n),
+            Func::Sqrt(a) => format!("csqrt{}({})", suffix, a.gen_inner(suffix)),
+            Func::Exp(a) => format!("cexp{}({})", suffix, a.gen_inner(suffix)),
+            Func::Ln(a) => format!("clog{}({})", suffix, a.gen_inner(suffix)),
+            Func::Sin(a) => format!("csin{}({})", suffix, a.gen_inner(suffix)),
+            Func::Cos(a) => format!("ccos{}({})", suffix, a.gen_inner(suffix)),
+            Func::Tan(a) => format!("ctan{}({})", suffix, a.gen_inner(suffix)),
+        }
+    }
+
+    pub fn polynomial(&self) -> Option<Vec<Complex<f64>>> {
+        let mut p = self.poly_inner()?;
+        while p.last().is_some_and(|a| a.norm_sqr() == 0.0) {
+            p.pop();
+        }
+        if p.len() > 1 && p.iter().all(|a| a.re.is_finite() && a.im.is_finite()) {
+            Some(p)
+        } else {
+            None
+        }
+    }
+
+    pub fn constant(&self) -> Option<Complex<f64>> {
+        let p = self.poly_inner()?;
+        if p[1..].iter().all(|a| a.norm_sqr() == 0.0) {
+            Some(p[0])
+        } else {
+            None
+        }
+    }
+
+    fn poly_inner(&self) -> Option<Vec<Complex<f64>>> {
+        let zero = Complex::new(0.0, 0.0);
+        let add = |a: Vec<Complex<f64>>, b: Vec<Complex<f64>>, sign: f64| {
+            (0..a.len().max(b.len()))
+                .map(|i| {
+                    a.get(i).copied().unwrap_or(zero) + b.get(i).copied().unwrap_or(zero) * sign
+                })
+                .collect::<Vec<_>>()
+        };
+        let mul = |a: &[Complex<f64>], b: &[Complex<f64>]| {
+            if a.len() + b.len() > MAX_DEGREE + 2 {
+                return None;
+            }
+            let mut r = vec![zero; a.len() + b.len() - 1];
+            for (i, x) in a.iter().enumerate() {
+                for (j, y) in b.iter().enumerate() {
+                    r[i + j] += x * y;
+                }
+            }
+            Some(r)
+        };
+
+        match self {
+            Func::Arg => Some(vec![zero, Complex::new(1.0, 0.0)]),
+            Func::Num(n) => Some(vec![Complex::new(*n, 0.0)]),
+            Func::Im => Some(vec![Complex::new(0.0, 1.0)]),
+            Func::Add(a, b) => Some(add(a.poly_inner()?, b.poly_inner()?, 1.0)),
+            Func::Sub(a, b) => Some(add(a.poly_inner()?, b.poly_inner()?, -1.0)),
+            Func::Mul(a, b) => mul(&a.poly_inner()?, &b.poly_inner()?),
+            Func::Div(a, b) => match b.poly_inner()?.as_slice() {
+                [c] => Some(a.poly_inner()?.into_iter().map(|x| x / c).collect()),
+                _ => None,
+            },
+            Func::PowI(a, n) if *n >= 0 => {
+                let (mut base, mut res, mut n) =
+                    (a.poly_inner()?, vec![Complex::new(1.0, 0.0)], *n);
+                while n > 0 {
+                    if n & 1 == 1 {
+                        res = mul(&res, &base)?;
+                    }
+                    n >>= 1;
+                    if n > 0 {
+                        base = mul(&base, &base)?;
+                    }
+                }
+                Some(res)
+            }
+            _ => None,
         }
     }
 
@@ -296,6 +411,10 @@ fn eval_func(expression: Pairs<Rule>) -> Func {
             ),
             Rule::arg => Func::Arg,
             Rule::num => Func::Num(pair.as_str().parse::<f64>().unwrap()),
+            Rule::imag => {
+                let num = pair.into_inner().next().unwrap();
+                Func::Num(num.as_str().parse::<f64>().unwrap()) * Func::Im
+            }
             Rule::pi => Func::Num(std::f64::consts::PI),
             Rule::e => Func::Num(std::f64::consts::E),
             Rule::im => Func::Im,
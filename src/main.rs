@@ -1,7 +1,6 @@
 use clap::{App, Arg};
 use num_complex::Complex;
 use rayon::prelude::*;
-use std::cmp::max;
 use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::BufWriter;
@@ -43,210 +42,484 @@ impl std::ops::Add<Color> for Color {
     }
 }
 
+mod antialias;
+mod argument;
+mod cluster;
 mod coord;
+mod dd;
 mod func;
 mod palette;
-
+mod poly;
+mod projection;
+mod real;
+mod root;
+mod stats;
+
+use crate::antialias::*;
+use crate::argument::*;
+use crate::cluster::*;
 use crate::coord::*;
+use crate::dd::DD;
 use crate::func::*;
 use crate::palette::*;
+use crate::poly::*;
+use crate::projection::*;
+use crate::real::{tolerance, Real};
+use crate::root::*;
+use crate::stats::*;
 
 const PRECISION: f64 = 1e-10;
 const ROOT_PRECISION: f64 = 1e-5;
-const ROOT_ITER: u16 = 256;
+const PIN_PRECISION: f64 = 1e-3;
+const MIN_ROOT_PRECISION: f64 = 1e-8;
+const ROOT_ITER: u32 = 256;
 const CONTRAST: f64 = 4.0;
+const ROOT_GRID: u32 = 128;
+const ROOT_GRID_SCALE: f64 = 3.0;
+const REFERENCE_GRID: u32 = 64;
+const REFERENCE_SIZE: f64 = 2.0;
+const AUTO_SEARCH: f64 = 4.0;
+const AUTO_SEARCH_MAX: f64 = 1024.0;
+const MARGIN: f64 = 0.2;
+const AA_ITER_DIFF: u32 = 2;
 
 const PIXEL_COUNT_FREQ: Duration = Duration::from_millis(1000);
 
+struct Attractors {
+    roots: Vec<Cluster>,
+    cycles: Vec<Cluster>,
+    colors: Vec<Color>,
+    index: RootIndex,
+}
+
+fn attractor_index(outcome: Outcome, attractors: &Attractors, params: &Params) -> Option<usize> {
+    match outcome {
+        Outcome::Converged { root, .. } => attractors.index.nearest(root),
+        Outcome::Cycle { point, period } => attractors
+            .cycles
+            .iter()
+            .position(|x| x.tag == period && (x.center - point).norm() < params.root_precision)
+            .map(|x| attractors.roots.len() + x),
+        _ => None,
+    }
+}
+
 fn find_newton(
-    x: Complex<f64>,
-    roots: &Option<Vec<Complex<f64>>>,
-    palette: Option<&(Vec<Color>, Color)>,
+    outcome: Outcome,
+    attractors: &Option<Attractors>,
+    palette: Option<&Palette>,
     shadow: f64,
+    params: &Params,
 ) -> Color {
-    let (root, dep) = find_root(x);
+    let shade =
+        |palette: &Palette| match attractor_index(outcome, attractors.as_ref().unwrap(), params) {
+            Some(x) => {
+                attractors.as_ref().unwrap().colors[x] * (1.0 - shadow) + palette.default * shadow
+            }
+            None => match outcome {
+                Outcome::Cycle { .. } => palette.cycle,
+                _ => palette.default,
+            },
+        };
 
-    match root {
-        None => {
-            if let Some((_, defcol)) = palette {
-                *defcol
+    match outcome {
+        Outcome::MaxIter | Outcome::Outside => {
+            if let Some(palette) = palette {
+                palette.default
             } else {
                 Color(0, 0, 0)
             }
         }
-        Some(root) => match palette {
-            Some((palette, defcol)) => match roots
-                .as_ref()
-                .unwrap()
-                .iter()
-                .enumerate()
-                .find(|x| (*x.1 - root).norm() < ROOT_PRECISION)
-            {
-                Some((x, _)) => palette[x % palette.len()] * (1.0 - shadow) + *defcol * shadow,
-                None => *defcol,
-            },
-            None => {
-                Color(255, 255, 255) * (1.0 - dep as f64 / ROOT_ITER as f64 * CONTRAST).max(0.0)
+        Outcome::Escaped => palette.map_or(Color(0, 0, 0), |palette| palette.escaped),
+        Outcome::DerivativeVanished => palette.map_or(Color(0, 0, 0), |palette| palette.vanished),
+        Outcome::NaN => palette.map_or(Color(0, 0, 0), |palette| palette.nan),
+        Outcome::Cycle { .. } => palette.map_or(Color(0, 0, 0), shade),
+        Outcome::Converged { iters, smooth, .. } => {
+            let iters = if params.smooth { smooth } else { iters as f64 };
+            let brightness = (1.0 - iters / params.iter as f64 * params.contrast).max(0.0);
+            match palette {
+                Some(palette) if params.smooth => shade(palette) * brightness,
+                Some(palette) => shade(palette),
+                None => Color(255, 255, 255) * brightness,
             }
-        },
-    }
-}
-
-fn sort_float(v: &mut Vec<Complex<f64>>) {
-    let mut i = 0;
-    let mut j = 0;
-    while i < v.len() {
-        if !v[i].re.is_nan() && !v[i].im.is_nan() {
-            v[j] = v[i];
-            j += 1;
         }
-        i += 1;
     }
+}
 
-    v.resize(j, Complex::default());
-
-    //println!("{:?}", v);
+fn get_stats(view: &View, outcomes: &[Outcome], attractors: &Attractors, params: &Params) -> Stats {
+    let color = |x: usize| attractors.colors.get(x).copied();
+    let mut stats = Stats {
+        width: view.width,
+        height: view.height,
+        roots: attractors
+            .roots
+            .iter()
+            .enumerate()
+            .map(|(x, root)| Basin::new(root.center, root.tag, color(x)))
+            .collect(),
+        cycles: attractors
+            .cycles
+            .iter()
+            .enumerate()
+            .map(|(x, cycle)| {
+                Basin::new(cycle.center, cycle.tag, color(attractors.roots.len() + x))
+            })
+            .collect(),
+        ..Stats::default()
+    };
 
-    v.sort_by(|a, b| {
-        if a.re.partial_cmp(&b.re).unwrap() == std::cmp::Ordering::Equal
-            || (a.re - b.re).abs() < ROOT_PRECISION
-        {
-            if a.im.partial_cmp(&b.im).unwrap() == std::cmp::Ordering::Equal
-                || (a.im - b.im).abs() < ROOT_PRECISION
-            {
-                std::cmp::Ordering::Equal
-            } else {
-                a.im.partial_cmp(&b.re).unwrap()
+    for &outcome in outcomes {
+        match (outcome, attractor_index(outcome, attractors, params)) {
+            (Outcome::Converged { iters, .. }, Some(x)) => {
+                let basin = &mut stats.roots[x];
+                basin.pixels += 1;
+                basin.iters += iters as u64;
+                basin.max_iter = basin.max_iter.max(iters);
             }
-        } else {
-            a.re.partial_cmp(&b.im).unwrap()
-        }
-    });
-}
-
-fn sort_float_rev(v: &mut [Complex<f64>]) {
-    v.sort_by(|a, b| {
-        if a.im.partial_cmp(&b.im).unwrap() == std::cmp::Ordering::Equal
-            || (a.im - b.im).abs() < ROOT_PRECISION
-        {
-            if a.re.partial_cmp(&b.re).unwrap() == std::cmp::Ordering::Equal
-                || (a.re - b.re).abs() < ROOT_PRECISION
-            {
-                std::cmp::Ordering::Equal
-            } else {
-                a.re.partial_cmp(&b.re).unwrap()
+            (Outcome::Converged { .. }, None) => stats.unmatched += 1,
+            (Outcome::Cycle { .. }, x) => {
+                stats.cycle += 1;
+                if let Some(x) = x {
+                    stats.cycles[x - attractors.roots.len()].pixels += 1;
+                }
             }
-        } else {
-            a.im.partial_cmp(&b.im).unwrap()
+            (Outcome::MaxIter, _) => stats.non_converged += 1,
+            (Outcome::Escaped, _) => stats.escaped += 1,
+            (Outcome::DerivativeVanished, _) => stats.vanished += 1,
+            (Outcome::NaN, _) => stats.nan += 1,
+            (Outcome::Outside, _) => stats.outside += 1,
         }
-    });
+    }
+    stats
 }
 
-fn find_root(mut x: Complex<f64>) -> (Option<Complex<f64>>, u16) {
-    match (0..ROOT_ITER)
-        .map(|i| {
-            let t = x;
-
-            let fc = unsafe { F_FUNC(t) };
-            let gc = unsafe { G_FUNC(t) };
+fn root_tolerance(multiplicity: u32, params: &Params) -> f64 {
+    params
+        .root_precision
+        .max(params.precision.powf(1.0 / multiplicity as f64))
+}
 
-            x = t - fc / gc;
-            (i, fc)
-        })
-        .find(|(_, x)| x.norm() < PRECISION)
-    {
-        Some((i, _)) => (Some(x), i),
-        None => (None, ROOT_ITER),
-    }
+fn fixed_tolerance(multiplicity: u32, params: &Params) -> f64 {
+    ROOT_PRECISION.max(params.precision.powf(1.0 / multiplicity as f64))
 }
 
-fn uniq(x: &mut Option<Complex<f64>>, n: Complex<f64>) -> Option<Complex<f64>> {
-    let r = if let Some(x) = x {
-        if (n - *x).norm() < ROOT_PRECISION {
-            None
-        } else {
-            Some(n)
-        }
+fn find_outcomes<T: Real>(view: &View, verbose: bool, params: &Params) -> Vec<Outcome> {
+    let (width, height) = (view.width, view.height);
+
+    let counter = if verbose {
+        Some(count_pixels(
+            "Генерация фрактала: ",
+            (height * width) as usize,
+        ))
     } else {
-        Some(n)
+        None
     };
-    *x = Some(n);
-    r
+
+    (0..height)
+        .into_par_iter()
+        .flat_map(|i| {
+            (0..width)
+                .map(|j| {
+                    if let Some(ref counter) = counter {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    }
+                    match view.point::<T>(i, j) {
+                        Some(x) => find_root(x, params),
+                        None => Outcome::Outside,
+                    }
+                })
+                .collect::<Vec<_>>()
+                .into_par_iter()
+        })
+        .collect()
 }
 
-fn uniq_vec(mut v: Vec<Complex<f64>>) -> Vec<Complex<f64>> {
-    sort_float(&mut v);
-    let mut x = None;
-    v = v
-        .into_iter()
-        .filter_map(|root| uniq(&mut x, root))
-        .collect();
+fn is_boundary(a: Outcome, b: Outcome, params: &Params) -> bool {
+    match (a, b) {
+        (
+            Outcome::Converged {
+                root: x,
+                iters: n,
+                multiplicity: p,
+                ..
+            },
+            Outcome::Converged {
+                root: y,
+                iters: m,
+                multiplicity: q,
+                ..
+            },
+        ) => (x - y).norm() >= root_tolerance(p.max(q), params) || n.abs_diff(m) > AA_ITER_DIFF,
+        (
+            Outcome::Cycle {
+                point: x,
+                period: p,
+            },
+            Outcome::Cycle {
+                point: y,
+                period: q,
+            },
+        ) => p != q || (x - y).norm() >= params.root_precision,
+        _ => std::mem::discriminant(&a) != std::mem::discriminant(&b),
+    }
+}
 
-    sort_float_rev(&mut v);
-    x = None;
-    v.into_iter()
-        .filter_map(|root| uniq(&mut x, root))
+fn find_boundary(view: &View, outcomes: &[Outcome], params: &Params) -> Vec<usize> {
+    let (width, height) = (view.width as usize, view.height as usize);
+    (0..width * height)
+        .into_par_iter()
+        .filter(|&k| {
+            let (i, j) = (k / width, k % width);
+            (i.saturating_sub(1)..(i + 2).min(height))
+                .flat_map(|i| (j.saturating_sub(1)..(j + 2).min(width)).map(move |j| i * width + j))
+                .any(|l| is_boundary(outcomes[k], outcomes[l], params))
+        })
         .collect()
 }
 
-fn find_roots(
-    (x1, y1): (f64, f64),
-    (x2, y2): (f64, f64),
-    height: u32,
+fn find_samples<T: Real>(
+    view: &View,
+    pixels: &[usize],
+    offsets: &[(f64, f64)],
     verbose: bool,
-) -> Vec<Complex<f64>> {
-    let width = calculate_width((x1, y1), (x2, y2), height);
+    params: &Params,
+) -> Vec<Outcome> {
+    let width = view.width as usize;
 
     let counter = if verbose {
-        Some(count_pixels("Поиск корней: ", (height * width) as usize))
+        Some(count_pixels("Сглаживание: ", pixels.len()))
     } else {
         None
     };
 
-    let mut roots = uniq_vec(
-        (0..height)
-            .into_par_iter()
-            .flat_map(|i| {
-                uniq_vec(
-                    (0..width)
-                        .filter_map(|j| {
-                            if let Some(ref counter) = counter {
-                                counter.fetch_add(1, Ordering::Relaxed);
-                            }
-                            find_root(complex_by_coord(
-                                (i, height),
-                                (j, width),
-                                (x1, y1),
-                                (x2, y2),
-                            ))
-                            .0
-                        })
-                        .collect::<Vec<_>>(),
-                )
-                .into_par_iter()
-            })
-            .collect::<Vec<_>>(),
-    );
-
-    sort_float(&mut roots);
+    pixels
+        .par_iter()
+        .flat_map_iter(|&k| {
+            if let Some(ref counter) = counter {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+            let (i, j) = ((k / width) as u32, (k % width) as u32);
+            offsets
+                .iter()
+                .map(move |&offset| match view.sample::<T>(i, j, offset) {
+                    Some(x) => find_root(x, params),
+                    None => Outcome::Outside,
+                })
+        })
+        .collect()
+}
 
+fn reference_roots<T: Real>(params: &Params) -> Vec<Cluster> {
+    let size = DD::from(REFERENCE_SIZE);
+    let view = View::new((-size, -size), (size, size), REFERENCE_GRID);
+    let mut roots: Vec<Cluster> = vec![];
+    for outcome in find_outcomes::<T>(&view, false, params) {
+        if let Outcome::Converged {
+            root, multiplicity, ..
+        } = outcome
+        {
+            let tolerance = fixed_tolerance(multiplicity, params);
+            if roots.iter().all(|x| (x.center - root).norm() >= tolerance) {
+                roots.push(Cluster::new(root, multiplicity));
+            }
+        }
+    }
     roots
 }
 
-fn complex_by_coord(
-    (i, h): (u32, u32),
-    (j, w): (u32, u32),
-    (x1, y1): (f64, f64),
-    (x2, y2): (f64, f64),
-) -> Complex<f64> {
-    Complex {
-        re: x1 + (x2 - x1) * j as f64 / w as f64,
-        im: y1 + (y2 - y1) * i as f64 / h as f64,
+fn find_roots<T: Real>(
+    view: &View,
+    outcomes: &[Outcome],
+    poly: Option<&[Complex<f64>]>,
+    palette: Option<&Palette>,
+    verbose: bool,
+    params: &Params,
+) -> Attractors {
+    let grid = if poly.is_none() || params.cycles {
+        find_outcomes::<T>(&view.grid(ROOT_GRID_SCALE, ROOT_GRID), false, params)
+    } else {
+        vec![]
+    };
+
+    let (roots, cycles): (Vec<_>, Vec<_>) = outcomes
+        .par_chunks(view.width as usize)
+        .chain(grid.par_chunks(ROOT_GRID as usize))
+        .map(|row| {
+            let (mut roots, mut cycles) = (vec![], vec![]);
+            for outcome in row {
+                match *outcome {
+                    Outcome::Converged {
+                        root, multiplicity, ..
+                    } if poly.is_none() => roots.push(Cluster::new(root, multiplicity)),
+                    Outcome::Cycle { point, period } => cycles.push(Cluster::new(point, period)),
+                    _ => (),
+                }
+            }
+            (
+                cluster(&roots, params.root_precision),
+                cluster_by_tag(&cycles, params.root_precision),
+            )
+        })
+        .unzip();
+
+    let mut roots = match poly {
+        Some(poly) => {
+            if verbose {
+                eprintln!(
+                    "Функция - многочлен степени {}, корни найдены методом Аберта",
+                    poly.len() - 1
+                );
+            }
+            poly_roots(poly, params.root_precision)
+        }
+        None => cluster(&roots.concat(), params.root_precision),
+    };
+    let ranks = match params.order {
+        RootOrder::Arg => {
+            sort_arg(&mut roots, params.root_precision);
+            vec![]
+        }
+        RootOrder::Lex => {
+            sort_lex(&mut roots, params.root_precision);
+            vec![]
+        }
+        RootOrder::Grid => {
+            let reference = reference_roots::<T>(params);
+            let index = RootIndex::new(&reference, |tag| fixed_tolerance(tag, params));
+            let rank = |root: &Cluster| index.nearest(root.center);
+            sort_by_rank(&mut roots, rank, params.root_precision);
+            let mut missing = reference.len()..;
+            roots
+                .iter()
+                .map(|root| rank(root).unwrap_or_else(|| missing.next().unwrap()))
+                .collect()
+        }
+    };
+
+    let cycles = cluster_by_tag(&cycles.concat(), params.root_precision);
+
+    if view.is_linear() && !view.needs_extended() {
+        check_root_count(view, &roots, verbose);
+    }
+
+    let index = RootIndex::new(&roots, |tag| root_tolerance(tag, params));
+    let colors = match palette {
+        Some(palette) => {
+            let mut pins = vec![None; roots.len()];
+            let index = RootIndex::new(&roots, |tag| {
+                PIN_PRECISION.max(fixed_tolerance(tag, params))
+            });
+            for &(point, color) in &palette.pins {
+                match index.nearest(point) {
+                    Some(x) => pins[x] = Some(color),
+                    None => eprintln!("Предупреждение: корень {} из --root-color не найден", point),
+                }
+            }
+            let mut sequence = ranks.iter().max().map_or(0, |x| x + 1)..;
+            pins.into_iter()
+                .chain(cycles.iter().map(|_| None))
+                .enumerate()
+                .map(|(x, pin)| {
+                    pin.unwrap_or_else(|| {
+                        let slot = ranks
+                            .get(x)
+                            .copied()
+                            .unwrap_or_else(|| sequence.next().unwrap());
+                        palette.colors[slot % palette.colors.len()]
+                    })
+                })
+                .collect()
+        }
+        None => vec![],
+    };
+
+    if verbose {
+        for root in &roots {
+            eprintln!("Найден корень {} кратности {}", root.center, root.tag);
+        }
+        for cycle in &cycles {
+            eprintln!(
+                "Найден цикл периода {}, содержащий точку {}",
+                cycle.tag, cycle.center
+            );
+        }
+    }
+
+    Attractors {
+        roots,
+        cycles,
+        colors,
+        index,
+    }
+}
+
+fn find_view(height: u32, margin: f64, aspect: f64, params: &Params) -> View {
+    let mut size = AUTO_SEARCH;
+    let roots = loop {
+        let search = View::new(
+            (DD::from(-size), DD::from(-size)),
+            (DD::from(size), DD::from(size)),
+            ROOT_GRID,
+        );
+        let roots: Vec<_> = find_outcomes::<f64>(&search, false, params)
+            .into_iter()
+            .filter_map(|outcome| match outcome {
+                Outcome::Converged {
+                    root, multiplicity, ..
+                } if root.re.abs() <= size && root.im.abs() <= size => {
+                    Some(Cluster::new(root, multiplicity))
+                }
+                _ => None,
+            })
+            .collect();
+        if !roots.is_empty() || size >= AUTO_SEARCH_MAX {
+            break cluster(&roots, params.root_precision);
+        }
+        size *= 2.0;
+    };
+
+    if roots.is_empty() {
+        eprintln!("Предупреждение: корни не найдены, используется область по умолчанию");
+        return View::around(
+            Complex::new(-1.0, -1.0),
+            Complex::new(1.0, 1.0),
+            0.0,
+            aspect,
+            height,
+        );
     }
+    let (min, max) = roots
+        .iter()
+        .fold((roots[0].center, roots[0].center), |(min, max), root| {
+            (
+                Complex::new(min.re.min(root.center.re), min.im.min(root.center.im)),
+                Complex::new(max.re.max(root.center.re), max.im.max(root.center.im)),
+            )
+        });
+    View::around(min, max, margin, aspect, height)
 }
 
-fn calculate_width((x1, y1): (f64, f64), (x2, y2): (f64, f64), height: u32) -> u32 {
-    max(((x2 - x1) / (y2 - y1) * height as f64) as u32, 1)
+fn check_root_count(view: &View, roots: &[Cluster], verbose: bool) {
+    let inside: u32 = roots
+        .iter()
+        .filter(|root| view.contains(root.center))
+        .map(|root| root.tag)
+        .sum();
+
+    match count_zeros(view.corners()) {
+        Some(count) if count != inside as i64 => eprintln!(
+            "Предупреждение: по принципу аргумента в области {} нулей (за вычетом полюсов), а найдено корней с учётом кратности: {}",
+            count, inside
+        ),
+        Some(count) if verbose => eprintln!(
+            "По принципу аргумента в области {} нулей (за вычетом полюсов)",
+            count
+        ),
+        None if verbose => eprintln!(
+            "Не удалось проверить число корней по принципу аргумента: функция обращается в ноль или не определена у границы области"
+        ),
+        _ => (),
+    }
 }
 
 fn count_pixels(intro: &'static str, max: usize) -> Arc<AtomicUsize> {
@@ -268,40 +541,14 @@ fn count_pixels(intro: &'static str, max: usize) -> Arc<AtomicUsize> {
     counter_clone
 }
 
-fn get_shadow(
-    z1: (f64, f64),
-    z2: (f64, f64),
-    height: u32,
-    verbose: bool,
-) -> HashMap<(u32, u32), u32> {
-    let width = calculate_width(z1, z2, height);
+fn get_shadow(view: &View, outcomes: &[Outcome]) -> HashMap<(u32, u32), u32> {
+    let (width, height) = (view.width, view.height);
 
-    let counter = if verbose {
-        Some(count_pixels("Рассчёт теней: ", (height * width) as usize))
-    } else {
-        None
-    };
-
-    let mut buf: VecDeque<_> = (0..height)
-        .into_par_iter()
-        .flat_map(|i| {
-            (0..width)
-                .filter_map(|j| {
-                    if let Some(counter) = counter.as_ref() {
-                        counter.fetch_add(1, Ordering::Relaxed);
-                    }
-                    if find_root(complex_by_coord((i, height), (j, width), z1, z2))
-                        .0
-                        .is_none()
-                    {
-                        Some(((i, j), 0))
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-                .into_par_iter()
-        })
+    let mut buf: VecDeque<_> = outcomes
+        .iter()
+        .enumerate()
+        .filter(|(_, outcome)| !outcome.is_attracted())
+        .map(|(k, _)| ((k as u32 / width, k as u32 % width), 0))
         .collect();
 
     let mut res: HashMap<(u32, u32), u32> = buf.iter().copied().collect();
@@ -330,72 +577,103 @@ fn get_shadow(
     res
 }
 
-fn newton(
-    z1: (f64, f64),
-    z2: (f64, f64),
-    palette: Option<&(Vec<Color>, Color)>,
+fn newton<T: Real>(
+    view: &View,
+    poly: Option<&[Complex<f64>]>,
+    palette: Option<&Palette>,
     needs_shadow: Option<f64>,
-    height: u32,
+    needs_stats: bool,
     verbose: bool,
-    negate: bool,
-) -> (u32, u32, Vec<u8>) {
-    let width = calculate_width(z1, z2, height);
-    let roots = if palette.is_some() {
-        Some(find_roots(z1, z2, height, verbose))
+    params: &Params,
+) -> (u32, u32, Vec<u8>, Option<Stats>) {
+    let (width, height) = (view.width, view.height);
+    let outcomes = find_outcomes::<T>(view, verbose, params);
+
+    let attractors = if palette.is_some() || needs_stats {
+        Some(find_roots::<T>(
+            view, &outcomes, poly, palette, verbose, params,
+        ))
     } else {
         None
     };
 
     let shadow = if needs_shadow.is_some() {
-        get_shadow(z1, z2, height, verbose)
+        get_shadow(view, &outcomes)
     } else {
         HashMap::new()
     };
 
-    let counter = if verbose {
-        Some(count_pixels(
-            "Генерация фрактала: ",
-            (height * width) as usize,
-        ))
-    } else {
-        None
+    let stats = attractors
+        .as_ref()
+        .filter(|_| needs_stats)
+        .map(|attractors| get_stats(view, &outcomes, attractors, params));
+
+    let offsets = params
+        .aa
+        .filter(|aa| aa.size > 1)
+        .map_or(vec![], Antialias::offsets);
+    let pixels = match params.aa {
+        _ if offsets.is_empty() => vec![],
+        Some(aa) if aa.adaptive => {
+            let pixels = find_boundary(view, &outcomes, params);
+            if verbose {
+                eprintln!(
+                    "Сглаживаются граничные пиксели: {} из {}",
+                    pixels.len(),
+                    outcomes.len()
+                );
+            }
+            pixels
+        }
+        _ => (0..outcomes.len()).collect(),
     };
+    let mut slots = vec![None; outcomes.len()];
+    for (slot, &k) in pixels.iter().enumerate() {
+        slots[k] = Some(slot);
+    }
+    let samples = find_samples::<T>(view, &pixels, &offsets, verbose, params);
 
     (
         width,
         height,
-        (0..height)
-            .into_par_iter()
-            .flat_map(|i| {
-                (0..width)
-                    .flat_map(|j| {
-                        let Color(r, g, b) = find_newton(
-                            complex_by_coord((i, height), (j, width), z1, z2),
-                            &roots,
-                            palette,
-                            match shadow.get(&(i, j)) {
-                                Some(&x) => {
-                                    (-(x as f64) * needs_shadow.unwrap() / height as f64).exp()
-                                }
-                                None => 0.0,
-                            },
-                        );
-                        if let Some(ref counter) = counter {
-                            counter.fetch_add(1, Ordering::Relaxed);
-                        }
-                        if negate {
-                            vec![255 - r, 255 - g, 255 - b]
-                        } else {
-                            vec![r, g, b]
-                        }
-                    })
-                    .collect::<Vec<_>>()
-                    .into_par_iter()
+        outcomes
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(k, &outcome)| {
+                let (i, j) = (k as u32 / width, k as u32 % width);
+                let shadow = match shadow.get(&(i, j)) {
+                    Some(&x) => (-(x as f64) * needs_shadow.unwrap() / height as f64).exp(),
+                    None => 0.0,
+                };
+                let color = |outcome| find_newton(outcome, &attractors, palette, shadow, params);
+                let n = offsets.len();
+                let Color(r, g, b) = match slots[k] {
+                    Some(slot) => {
+                        blend(samples[slot * n..(slot + 1) * n].iter().map(|&x| color(x)))
+                    }
+                    None => color(outcome),
+                };
+                [r, g, b]
             })
             .collect::<Vec<_>>(),
+        stats,
     )
 }
 
+fn validate_positive_float(x: String) -> Result<(), String> {
+    match x.trim().parse::<f64>() {
+        Ok(x) if x > 0.0 => Ok(()),
+        _ => Err("Параметр должен быть положительным числом".to_string()),
+    }
+}
+
+fn validate_positive_int(x: String) -> Result<(), String> {
+    match x.trim().parse::<u32>() {
+        Ok(x) if x > 0 => Ok(()),
+        _ => Err("Параметр должен быть целым положительным числом".to_string()),
+    }
+}
+
 fn write_png(path: &str, (w, h): (u32, u32), data: &[u8]) -> Result<(), std::io::Error> {
     let path = Path::new(path);
     let file = File::create(path)?;
@@ -410,12 +688,22 @@ fn write_png(path: &str, (w, h): (u32, u32), data: &[u8]) -> Result<(), std::io:
     Ok(())
 }
 
+type CFunc = unsafe extern "C" fn(Complex<f64>) -> Complex<f64>;
+
 static mut LIB_FUNC: Option<libloading::Library> = None;
 
-static mut F_FUNC: libloading::Symbol<unsafe extern "C" fn(Complex<f64>) -> Complex<f64>> =
-    unsafe { std::mem::transmute(0usize) };
-static mut G_FUNC: libloading::Symbol<unsafe extern "C" fn(Complex<f64>) -> Complex<f64>> =
-    unsafe { std::mem::transmute(0usize) };
+static mut F_FUNC: Option<CFunc> = None;
+static mut G_FUNC: Option<CFunc> = None;
+
+type CFunc32 = unsafe extern "C" fn(Complex<f32>) -> Complex<f32>;
+
+static mut F32_FUNC: Option<CFunc32> = None;
+static mut G32_FUNC: Option<CFunc32> = None;
+
+type CFuncDD = unsafe extern "C" fn(*const f64, *mut f64);
+
+static mut FDD_FUNC: Option<CFuncDD> = None;
+static mut GDD_FUNC: Option<CFuncDD> = None;
 
 fn main() -> Result<(), std::io::Error> {
     let matches = App::new("Фракталы Ньютона")
@@ -434,6 +722,23 @@ fn main() -> Result<(), std::io::Error> {
                     _ => Err(String::from("Высота должна быть целым положтельным числом")),
                 }),
         )
+        .arg(
+            Arg::with_name("width")
+                .long("width")
+                .value_name("WIDTH")
+                .help("Устанавливает ширину результирующего изображения (по умолчанию определяется областью)")
+                .takes_value(true)
+                .validator(validate_positive_int),
+        )
+        .arg(
+            Arg::with_name("fit")
+                .long("fit")
+                .value_name("FIT")
+                .requires("width")
+                .help("Устанавливает способ вписывания области в изображение (stretch, contain, cover)")
+                .takes_value(true)
+                .validator(validate_fit),
+        )
         .arg(
             Arg::with_name("output")
                 .short("o")
@@ -447,21 +752,136 @@ fn main() -> Result<(), std::io::Error> {
                 .short("f")
                 .value_name("function")
                 .help("Устанавливает функцию, по которой строится фрактал")
-                .required(true)
+                .required_unless("lib")
+                .conflicts_with("lib")
                 .takes_value(true)
                 .validator(|f| match parse_func(&f) {
                     Ok(_) => Ok(()),
                     Err(e) => Err(format!("{}", e)),
                 }),
         )
+        .arg(
+            Arg::with_name("lib")
+                .long("lib")
+                .value_name("LIB")
+                .help("Загружает функцию func и, если есть, её производную diff из разделяемой библиотеки")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("method")
+                .long("method")
+                .value_name("METHOD")
+                .help("Устанавливает метод поиска корней (newton, secant, steffensen, damped)")
+                .takes_value(true)
+                .validator(validate_method),
+        )
+        .arg(
+            Arg::with_name("compare")
+                .long("compare")
+                .value_name("METHOD")
+                .help("Рисует слева фрактал для указанного метода, а справа - для основного")
+                .takes_value(true)
+                .validator(validate_method),
+        )
         .arg(
             Arg::with_name("coord")
                 .short("c")
-                .value_name("X1, Y1; X2, Y2")
+                .value_name("X1, Y1; X2, Y2 | Z1 .. Z2")
                 .help("Устанавливает координаты для отображения фрактала")
                 .takes_value(true)
                 .validator(validate_coord),
         )
+        .arg(
+            Arg::with_name("center")
+                .long("center")
+                .value_name("RE, IM")
+                .conflicts_with_all(&["coord", "auto_view"])
+                .help("Устанавливает центр изображения")
+                .takes_value(true)
+                .validator(validate_center),
+        )
+        .arg(
+            Arg::with_name("zoom")
+                .long("zoom")
+                .value_name("Z")
+                .requires("center")
+                .help("Устанавливает увеличение: высота области равна 2/Z (по умолчанию 1)")
+                .takes_value(true)
+                .validator(validate_zoom),
+        )
+        .arg(
+            Arg::with_name("rotate")
+                .long("rotate")
+                .value_name("DEG")
+                .requires("center")
+                .help("Поворачивает область на указанный угол в градусах против часовой стрелки")
+                .takes_value(true)
+                .validator(|x| match x.trim().parse::<f64>() {
+                    Ok(x) if x.is_finite() => Ok(()),
+                    _ => Err("Угол должен быть числом".to_string()),
+                }),
+        )
+        .arg(
+            Arg::with_name("auto_view")
+                .long("auto-view")
+                .conflicts_with("coord")
+                .help("Выбирает область изображения по оценкам корней многочлена или по найденным корням"),
+        )
+        .arg(
+            Arg::with_name("margin")
+                .long("margin")
+                .value_name("K")
+                .requires("auto_view")
+                .help("Устанавливает отступ вокруг корней в долях размера области (по умолчанию 0.2)")
+                .takes_value(true)
+                .validator(|x| match x.trim().parse::<f64>() {
+                    Ok(x) if x >= 0.0 => Ok(()),
+                    _ => Err("Отступ должен быть неотрицательным числом".to_string()),
+                }),
+        )
+        .arg(
+            Arg::with_name("aspect")
+                .long("aspect")
+                .value_name("W:H")
+                .conflicts_with("coord")
+                .help("Устанавливает соотношение сторон области (по умолчанию 1:1)")
+                .takes_value(true)
+                .validator(validate_aspect),
+        )
+        .arg(
+            Arg::with_name("legacy")
+                .long("legacy-orientation")
+                .help("Направляет мнимую ось вниз, как в старых версиях"),
+        )
+        .arg(
+            Arg::with_name("projection")
+                .long("projection")
+                .value_name("PROJECTION")
+                .conflicts_with("auto_view")
+                .help("Устанавливает проекцию изображения на комплексную плоскость (plane, exp, sphere)")
+                .takes_value(true)
+                .validator(validate_projection),
+        )
+        .arg(
+            Arg::with_name("mobius")
+                .long("mobius")
+                .value_name("A; B; C; D")
+                .conflicts_with("auto_view")
+                .help("Применяет после проекции преобразование Мёбиуса (az + b) / (cz + d)")
+                .takes_value(true)
+                .validator(validate_mobius),
+        )
+        .arg(
+            Arg::with_name("fast")
+                .long("fast")
+                .conflicts_with("extended")
+                .help("Включает быстрый режим одинарной точности для черновых изображений"),
+        )
+        .arg(
+            Arg::with_name("extended")
+                .long("extended")
+                .help("Включает расширенную точность (double-double) вне зависимости от масштаба"),
+        )
         .arg(
             Arg::with_name("palette")
                 .long("palette")
@@ -476,10 +896,119 @@ fn main() -> Result<(), std::io::Error> {
                 .requires("palette")
                 .help("Включает режим тени")
                 .takes_value(true)
-                .validator(|x| match x.trim().parse::<f64>() {
-                    Ok(x) if x > 0.0 => Ok(()),
-                    _ => Err("Параметр должен быть положительным числом".to_string()),
-                }),
+                .validator(validate_positive_float),
+        )
+        .arg(
+            Arg::with_name("root_color")
+                .long("root-color")
+                .value_name("ROOT=#RRGGBB[,ROOT=#RRGGBB...]")
+                .requires("palette")
+                .help("Закрепляет цвета за корнями, находящимися в указанных точках")
+                .takes_value(true)
+                .validator(validate_root_colors),
+        )
+        .arg(
+            Arg::with_name("root_order")
+                .long("root-order")
+                .value_name("ORDER")
+                .requires("palette")
+                .help("Устанавливает порядок назначения цветов корням (arg, lex, grid)")
+                .takes_value(true)
+                .validator(validate_root_order),
+        )
+        .arg(
+            Arg::with_name("stop")
+                .long("stop")
+                .value_name("RULE[+RULE...]")
+                .help("Устанавливает критерий остановки итерации (residual, step, relstep)")
+                .takes_value(true)
+                .validator(validate_stop),
+        )
+        .arg(
+            Arg::with_name("escape")
+                .long("escape")
+                .value_name("R")
+                .help("Устанавливает радиус, при выходе за который точка считается убежавшей")
+                .takes_value(true)
+                .validator(validate_positive_float),
+        )
+        .arg(
+            Arg::with_name("cycles")
+                .long("cycles")
+                .help("Включает поиск притягивающих циклов"),
+        )
+        .arg(
+            Arg::with_name("multiplicity")
+                .long("multiplicity")
+                .help("Включает ускорение сходимости к кратным корням с оценкой кратности на лету"),
+        )
+        .arg(
+            Arg::with_name("precision")
+                .long("precision")
+                .value_name("EPS")
+                .help("Устанавливает точность, с которой ищется корень (по умолчанию 1e-10)")
+                .takes_value(true)
+                .validator(validate_positive_float),
+        )
+        .arg(
+            Arg::with_name("root_precision")
+                .long("root-precision")
+                .value_name("EPS")
+                .help("Устанавливает расстояние, на котором корни считаются совпадающими (по умолчанию 1e-5 от размера области)")
+                .takes_value(true)
+                .validator(validate_positive_float),
+        )
+        .arg(
+            Arg::with_name("iter")
+                .long("iter")
+                .value_name("N")
+                .help("Устанавливает максимальное число итераций (по умолчанию 256)")
+                .takes_value(true)
+                .validator(validate_positive_int),
+        )
+        .arg(
+            Arg::with_name("contrast")
+                .long("contrast")
+                .value_name("K")
+                .help("Устанавливает контрастность чёрно-белого режима (по умолчанию 4)")
+                .takes_value(true)
+                .validator(validate_positive_float),
+        )
+        .arg(
+            Arg::with_name("aa")
+                .long("aa")
+                .value_name("N")
+                .help("Включает сглаживание: цвет пикселя усредняется по NxN точкам")
+                .takes_value(true)
+                .validator(validate_positive_int),
+        )
+        .arg(
+            Arg::with_name("aa_grid")
+                .long("aa-grid")
+                .value_name("GRID")
+                .requires("aa")
+                .help("Устанавливает сетку точек сглаживания (regular, rotated)")
+                .takes_value(true)
+                .validator(validate_sample_grid),
+        )
+        .arg(
+            Arg::with_name("aa_adaptive")
+                .long("aa-adaptive")
+                .requires("aa")
+                .help("Сглаживает только пиксели на границах областей притяжения"),
+        )
+        .arg(
+            Arg::with_name("smooth")
+                .long("smooth")
+                .help("Использует дробное число итераций: сглаживает чёрно-белый режим и затемняет цвета палитры"),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .long("stats")
+                .value_name("FILE.json|FILE.csv")
+                .help("Записывает найденные корни и статистику областей притяжения в файл")
+                .takes_value(true)
+                .validator(validate_stats),
         )
         .arg(
             Arg::with_name("verbose")
@@ -496,44 +1025,228 @@ fn main() -> Result<(), std::io::Error> {
 
     let height = matches.value_of("height").unwrap().trim().parse().unwrap();
     let path = matches.value_of("output").unwrap();
-    let f = parse_func(matches.value_of("function").unwrap()).unwrap();
-    let (start, end) = get_coord(&matches);
+    let f = matches.value_of("function").map(|f| parse_func(f).unwrap());
+    let lib = matches.value_of("lib");
+    let poly = f.as_ref().and_then(Func::polynomial);
+    let auto_view = matches.is_present("auto_view");
+    let margin = matches
+        .value_of("margin")
+        .map_or(MARGIN, |x| x.trim().parse().unwrap());
+    let aspect = matches
+        .value_of("aspect")
+        .map_or(1.0, |x| parse_aspect(x).unwrap());
+    let mut view = match &poly {
+        Some(poly) if auto_view => {
+            let r = cauchy_bound(poly).min(fujiwara_bound(poly));
+            View::around(
+                Complex::new(-r, -r),
+                Complex::new(r, r),
+                margin,
+                aspect,
+                height,
+            )
+        }
+        _ => get_centered(&matches, aspect, height).unwrap_or_else(|| {
+            let (start, end) = get_coord(&matches);
+            View::new(start, end, height)
+        }),
+    };
+    let fit = matches
+        .value_of("fit")
+        .map_or(Fit::Contain, |x| Fit::from_name(x).unwrap());
+    let legacy = matches.is_present("legacy");
+    let projection = matches
+        .value_of("projection")
+        .map_or(Projection::Plane, |x| Projection::from_name(x).unwrap());
+    let mobius = matches.value_of("mobius").map(get_mobius);
+    let fit_view = |view: View| {
+        let view = match matches.value_of("width") {
+            Some(width) => view.fit(width.trim().parse().unwrap(), fit),
+            None => view,
+        };
+        let view = if legacy { view.legacy() } else { view };
+        view.project(projection, mobius)
+    };
+    view = fit_view(view);
+    let mut fast = matches.is_present("fast");
+    let mut extended = matches.is_present("extended") || (!fast && view.needs_extended());
+    let get_params = |view: &View| Params {
+        method: matches
+            .value_of("method")
+            .map_or(Method::Newton, |m| Method::from_name(m).unwrap()),
+        stop: matches
+            .value_of("stop")
+            .map_or(Stop::default(), |s| Stop::from_names(s).unwrap()),
+        precision: matches
+            .value_of("precision")
+            .map_or(PRECISION, |x| x.trim().parse().unwrap()),
+        root_precision: matches.value_of("root_precision").map_or(
+            (ROOT_PRECISION * view.span()).max(MIN_ROOT_PRECISION),
+            |x| x.trim().parse().unwrap(),
+        ),
+        escape: matches
+            .value_of("escape")
+            .map(|x| x.trim().parse().unwrap()),
+        cycles: matches.is_present("cycles"),
+        multiplicity: matches.is_present("multiplicity"),
+        order: matches
+            .value_of("root_order")
+            .map_or(RootOrder::Lex, |x| RootOrder::from_name(x).unwrap()),
+        iter: matches
+            .value_of("iter")
+            .map_or(ROOT_ITER, |x| x.trim().parse().unwrap()),
+        contrast: matches
+            .value_of("contrast")
+            .map_or(CONTRAST, |x| x.trim().parse().unwrap()),
+        aa: matches.value_of("aa").map(|x| Antialias {
+            size: x.trim().parse().unwrap(),
+            grid: matches
+                .value_of("aa_grid")
+                .map_or(SampleGrid::Regular, |x| SampleGrid::from_name(x).unwrap()),
+            adaptive: matches.is_present("aa_adaptive"),
+        }),
+        smooth: matches.is_present("smooth"),
+    };
+    let mut params = get_params(&view);
     let verbose = matches.is_present("verbose");
-    let palette = matches.value_of("palette").map(get_palette);
+    let palette = matches.value_of("palette").map(|p| Palette {
+        pins: matches
+            .value_of("root_color")
+            .map_or(vec![], get_root_colors),
+        ..get_palette(p)
+    });
     let shadow = matches
         .value_of("shadow")
         .map(|x| x.trim().parse().unwrap());
     let negate = matches.is_present("negate");
-
-    let g = f.clone().diff();
+    let stats = matches.value_of("stats");
+    let compare = matches
+        .value_of("compare")
+        .map(|m| Method::from_name(m).unwrap());
+    let needs_diff = params.method.needs_diff() || compare.is_some_and(Method::needs_diff);
 
     let time = std::time::Instant::now();
 
-    {
-        let mut file = File::create("jit.c").unwrap();
-        writeln!(file, "{}", f.genc("func")).unwrap();
-        writeln!(file, "{}", g.genc("diff")).unwrap();
-        std::mem::drop(file);
+    let library = match (f, lib) {
+        (Some(f), _) => {
+            let compile = |quad: bool| {
+                let mut file = File::create("jit.c").unwrap();
+                writeln!(file, "{}", f.genc("func")).unwrap();
+                if fast {
+                    writeln!(file, "{}", f.genc_float("func_f32")).unwrap();
+                }
+                if quad {
+                    writeln!(file, "{}", f.genc_quad("func_dd")).unwrap();
+                }
+                if needs_diff {
+                    let g = f.clone().diff();
+                    writeln!(file, "{}", g.genc("diff")).unwrap();
+                    if fast {
+                        writeln!(file, "{}", g.genc_float("diff_f32")).unwrap();
+                    }
+                    if quad {
+                        writeln!(file, "{}", g.genc_quad("diff_dd")).unwrap();
+                    }
+                }
+                std::mem::drop(file);
 
-        Command::new("sh")
-            .arg("-c")
-            .arg("gcc -O3 -fPIC -c jit.c && gcc -shared -o jit.so jit.o -lm")
-            .output()
-            .unwrap();
+                Command::new("sh")
+                    .arg("-c")
+                    .arg(if quad {
+                        "gcc -O3 -fPIC -c jit.c && gcc -shared -o jit.so jit.o -lquadmath -lm"
+                    } else {
+                        "gcc -O3 -fPIC -c jit.c && gcc -shared -o jit.so jit.o -lm"
+                    })
+                    .output()
+                    .unwrap()
+                    .status
+                    .success()
+            };
 
-        unsafe {
-            LIB_FUNC = Some(libloading::Library::new("./jit.so").unwrap());
+            let compiled = (extended && compile(true)) || compile(false);
 
-            F_FUNC = LIB_FUNC.as_mut().unwrap().get(b"func").unwrap();
-            G_FUNC = LIB_FUNC.as_mut().unwrap().get(b"diff").unwrap();
-        };
+            let library = if compiled {
+                unsafe { libloading::Library::new("./jit.so").map_err(std::io::Error::other) }
+            } else {
+                Err(std::io::Error::other("Не удалось скомпилировать функцию"))
+            };
+
+            Command::new("sh")
+                .arg("-c")
+                .arg("rm -f jit.c jit.o jit.so")
+                .output()
+                .unwrap();
+
+            library?
+        }
+        (None, Some(lib)) => unsafe {
+            libloading::Library::new(lib).map_err(std::io::Error::other)?
+        },
+        (None, None) => unreachable!(),
+    };
+
+    unsafe {
+        let g = library.get::<CFunc>(b"diff").ok().map(|g| *g);
+        if needs_diff && g.is_none() {
+            return Err(std::io::Error::other(
+                "Для выбранного метода необходима производная функции (diff)",
+            ));
+        }
+
+        F_FUNC = Some(
+            *library
+                .get::<CFunc>(b"func")
+                .map_err(std::io::Error::other)?,
+        );
+        G_FUNC = g;
+
+        let f32 = library.get::<CFunc32>(b"func_f32").ok().map(|f| *f);
+        let g32 = library.get::<CFunc32>(b"diff_f32").ok().map(|g| *g);
+        if fast && (f32.is_none() || (needs_diff && g32.is_none())) {
+            eprintln!("Библиотека не содержит функций func_f32/diff_f32, быстрый режим отключён");
+            fast = false;
+        }
+        F32_FUNC = f32;
+        G32_FUNC = g32;
+
+        let fdd = library.get::<CFuncDD>(b"func_dd").ok().map(|f| *f);
+        let gdd = library.get::<CFuncDD>(b"diff_dd").ok().map(|g| *g);
+        if extended && (fdd.is_none() || (needs_diff && gdd.is_none())) {
+            eprintln!(
+                "Библиотека не содержит функций func_dd/diff_dd, расширенная точность отключена"
+            );
+            extended = false;
+        }
+        FDD_FUNC = fdd;
+        GDD_FUNC = gdd;
+
+        LIB_FUNC = Some(library);
+    }
+
+    if auto_view && poly.is_none() {
+        view = fit_view(find_view(height, margin, aspect, &params));
+        params = get_params(&view);
+    }
+
+    if verbose && auto_view {
+        let corners = view.corners();
+        let (min, max) = corners[1..]
+            .iter()
+            .fold((corners[0], corners[0]), |(min, max), x| {
+                (
+                    Complex::new(min.re.min(x.re), min.im.min(x.im)),
+                    Complex::new(max.re.max(x.re), max.im.max(x.im)),
+                )
+            });
+        eprintln!(
+            "Выбрана область {}, {}; {}, {}",
+            min.re, min.im, max.re, max.im
+        );
     }
 
-    Command::new("sh")
-        .arg("-c")
-        .arg("rm jit.c jit.o jit.so")
-        .output()
-        .unwrap();
+    if verbose && extended {
+        eprintln!("Используется расширенная точность (double-double)");
+    }
 
     if verbose {
         eprintln!("Функции скомпилированы за {:?}", time.elapsed());
@@ -541,15 +1254,72 @@ fn main() -> Result<(), std::io::Error> {
 
     let time = std::time::Instant::now();
 
-    let (w, h, v) = newton(
-        start,
-        end,
-        palette.as_ref(),
-        shadow,
-        height,
-        verbose,
-        negate,
-    );
+    let params = if fast {
+        Params {
+            precision: tolerance::<f32>(params.precision),
+            root_precision: params
+                .root_precision
+                .max(tolerance::<f32>(MIN_ROOT_PRECISION)),
+            ..params
+        }
+    } else {
+        params
+    };
+
+    let render = |params: &Params| {
+        if fast {
+            newton::<f32>(
+                &view,
+                poly.as_deref(),
+                palette.as_ref(),
+                shadow,
+                stats.is_some(),
+                verbose,
+                params,
+            )
+        } else if extended {
+            newton::<DD>(
+                &view,
+                poly.as_deref(),
+                palette.as_ref(),
+                shadow,
+                stats.is_some(),
+                verbose,
+                params,
+            )
+        } else {
+            newton::<f64>(
+                &view,
+                poly.as_deref(),
+                palette.as_ref(),
+                shadow,
+                stats.is_some(),
+                verbose,
+                params,
+            )
+        }
+    };
+
+    let (w, h, mut v, basins) = match compare {
+        Some(method) => {
+            let (w, h, left, _) = render(&Params { method, ..params });
+            let (_, _, right, basins) = render(&params);
+            (
+                2 * w,
+                h,
+                left.chunks(3 * w as usize)
+                    .zip(right.chunks(3 * w as usize))
+                    .flat_map(|(l, r)| l.iter().chain(r).copied())
+                    .collect(),
+                basins,
+            )
+        }
+        None => render(&params),
+    };
+
+    if negate {
+        v.iter_mut().for_each(|x| *x = 255 - *x);
+    }
 
     if verbose {
         eprintln!("Изображение сгенерировано за {:?}", time.elapsed());
@@ -557,7 +1327,14 @@ fn main() -> Result<(), std::io::Error> {
 
     let time = std::time::Instant::now();
 
+    if verbose {
+        eprintln!("Размер изображения: {}x{}", w, h);
+    }
+
     write_png(path, (w, h), &v)?;
+    if let (Some(path), Some(basins)) = (stats, basins) {
+        basins.write(path)?;
+    }
 
     if verbose {
         eprintln!("Изображение записано за {:?}", time.elapsed());
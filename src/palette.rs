@@ -1,13 +1,30 @@
+use num_complex::Complex;
 use pest::iterators::Pair;
 use pest::pratt_parser::{Assoc, Op, PrattParser};
 use pest::Parser;
 
+use crate::func::parse_func;
 use crate::Color;
 
 #[derive(Parser)]
 #[grammar = "palette.pest"]
 struct PaletteParser;
 
+pub const ESCAPED_COLOR: Color = Color(32, 32, 96);
+pub const CYCLE_COLOR: Color = Color(96, 32, 96);
+pub const VANISHED_COLOR: Color = Color(96, 96, 32);
+pub const NAN_COLOR: Color = Color(96, 32, 32);
+
+pub struct Palette {
+    pub colors: Vec<Color>,
+    pub default: Color,
+    pub escaped: Color,
+    pub cycle: Color,
+    pub vanished: Color,
+    pub nan: Color,
+    pub pins: Vec<(Complex<f64>, Color)>,
+}
+
 pub fn validate_palette(palette: String) -> Result<(), String> {
     match PaletteParser::parse(Rule::palette, &palette) {
         Ok(_) => Ok(()),
@@ -18,6 +35,33 @@ pub fn validate_palette(palette: String) -> Result<(), String> {
     }
 }
 
+fn parse_root_color(pin: &str) -> Result<(Complex<f64>, Color), String> {
+    let (root, color) = pin
+        .split_once('=')
+        .ok_or_else(|| format!("Ожидается КОРЕНЬ=#RRGGBB: {}", pin.trim()))?;
+    let root = parse_func(root)
+        .ok()
+        .and_then(|f| f.constant())
+        .ok_or_else(|| format!("Корень должен быть комплексным числом: {}", root.trim()))?;
+    let color = color
+        .trim()
+        .strip_prefix('#')
+        .and_then(|c| hex::decode(c).ok())
+        .filter(|v| v.len() == 3)
+        .ok_or_else(|| format!("Цвет должен иметь вид #RRGGBB: {}", color.trim()))?;
+    Ok((root, Color(color[0], color[1], color[2])))
+}
+
+pub fn validate_root_colors(pins: String) -> Result<(), String> {
+    pins.split(',').try_for_each(|pin| parse_root_color(pin).map(|_| ()))
+}
+
+pub fn get_root_colors(pins: &str) -> Vec<(Complex<f64>, Color)> {
+    pins.split(',')
+        .map(|pin| parse_root_color(pin).unwrap())
+        .collect()
+}
+
 lazy_static! {
     static ref PRATT_PARSER: PrattParser<Rule> = PrattParser::new()
         .op(
@@ -28,7 +72,11 @@ lazy_static! {
         .op(Op::postfix(Rule::EOI));
 }
 
-pub fn get_palette(palette_string: &str) -> (Vec<Color>, Color) {
+pub fn get_palette(palette_string: &str) -> Palette {
+    let (outcomes, pairs): (Vec<_>, Vec<_>) = PaletteParser::parse(Rule::palette, palette_string)
+        .unwrap()
+        .partition(|pair| pair.as_rule() == Rule::outcome_color);
+
     let (palette, defcol) = PRATT_PARSER
         .map_primary(|pair: Pair<Rule>| match pair.as_rule() {
             Rule::color => {
@@ -81,16 +129,36 @@ pub fn get_palette(palette_string: &str) -> (Vec<Color>, Color) {
             Rule::EOI => lhs,
             _ => unreachable!(),
         })
-        .parse(PaletteParser::parse(Rule::palette, palette_string).unwrap());
+        .parse(pairs.into_iter());
 
-    (
-        palette
+    let mut palette = Palette {
+        colors: palette
             .into_iter()
             .filter_map(|(c, h)| if h { None } else { Some(c) })
             .collect(),
-        match defcol {
+        default: match defcol {
             None => Color(0, 0, 0),
             Some((c, _)) => c,
         },
-    )
+        escaped: ESCAPED_COLOR,
+        cycle: CYCLE_COLOR,
+        vanished: VANISHED_COLOR,
+        nan: NAN_COLOR,
+        pins: vec![],
+    };
+
+    for pair in outcomes {
+        let mut inner = pair.into_inner();
+        let (outcome, color) = (inner.next().unwrap(), inner.next().unwrap());
+        let v = hex::decode(color.into_inner().as_str()).unwrap();
+        match outcome.as_str() {
+            "escaped" => palette.escaped = Color(v[0], v[1], v[2]),
+            "cycle" => palette.cycle = Color(v[0], v[1], v[2]),
+            "vanished" => palette.vanished = Color(v[0], v[1], v[2]),
+            "nan" => palette.nan = Color(v[0], v[1], v[2]),
+            _ => unreachable!(),
+        }
+    }
+
+    palette
 }
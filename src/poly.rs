@@ -0,0 +1,233 @@
+use num_complex::Complex;
+
+use crate::cluster::*;
+
+const ABERTH_ITER: u32 = 500;
+const ABERTH_PRECISION: f64 = 1e-14;
+const CLUSTER_PRECISION: f64 = 0.1;
+const MULTIPLE_SPREAD: f64 = 100.0;
+const SPLIT_FACTOR: f64 = 10.0;
+
+fn eval(coeffs: &[Complex<f64>], x: Complex<f64>) -> (Complex<f64>, Complex<f64>) {
+    let (mut p, mut dp) = (Complex::new(0.0, 0.0), Complex::new(0.0, 0.0));
+    for &a in coeffs.iter().rev() {
+        dp = dp * x + p;
+        p = p * x + a;
+    }
+    (p, dp)
+}
+
+pub fn cauchy_bound(coeffs: &[Complex<f64>]) -> f64 {
+    let n = coeffs.len() - 1;
+    let lead = coeffs[n].norm();
+    1.0 + coeffs[..n]
+        .iter()
+        .map(|a| a.norm() / lead)
+        .fold(0.0, f64::max)
+}
+
+pub fn fujiwara_bound(coeffs: &[Complex<f64>]) -> f64 {
+    let n = coeffs.len() - 1;
+    let lead = coeffs[n].norm();
+    (1..=n)
+        .map(|k| {
+            let a = coeffs[n - k].norm() / lead;
+            if k == n {
+                (a / 2.0).powf(1.0 / k as f64)
+            } else {
+                a.powf(1.0 / k as f64)
+            }
+        })
+        .fold(0.0, f64::max)
+        * 2.0
+}
+
+fn aberth(coeffs: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let n = coeffs.len() - 1;
+    let radius = fujiwara_bound(coeffs).max(f64::MIN_POSITIVE);
+    let mut z: Vec<_> = (0..n)
+        .map(|k| Complex::from_polar(radius, std::f64::consts::TAU * k as f64 / n as f64 + 0.4))
+        .collect();
+
+    for _ in 0..ABERTH_ITER {
+        let mut correction: f64 = 0.0;
+        for k in 0..n {
+            let (p, dp) = eval(coeffs, z[k]);
+            if p.norm_sqr() == 0.0 {
+                continue;
+            }
+            let ratio = p / dp;
+            let sum: Complex<f64> = (0..n)
+                .filter(|&j| j != k)
+                .map(|j| 1.0 / (z[k] - z[j]))
+                .sum();
+            let w = ratio / (1.0 - ratio * sum);
+            if w.re.is_finite() && w.im.is_finite() {
+                z[k] -= w;
+                correction = correction.max(w.norm() / (1.0 + z[k].norm()));
+            }
+        }
+        if correction < ABERTH_PRECISION {
+            break;
+        }
+    }
+    z
+}
+
+fn taylor(coeffs: &[Complex<f64>], c: Complex<f64>) -> Vec<Complex<f64>> {
+    let mut a = coeffs.to_vec();
+    let n = a.len();
+    for k in 0..n {
+        for j in (k..n - 1).rev() {
+            a[j] = a[j] + c * a[j + 1];
+        }
+    }
+    a
+}
+
+fn is_multiple(coeffs: &[Complex<f64>], roots: &[Cluster], c: Complex<f64>, eps: f64) -> bool {
+    let m = roots.len();
+    let spread = roots
+        .iter()
+        .map(|x| (x.center - c).norm())
+        .fold(0.0, f64::max);
+    let shifted = taylor(coeffs, c);
+    let r = c.norm().max(spread).max(eps);
+    let cond: f64 = coeffs
+        .iter()
+        .enumerate()
+        .map(|(i, a)| a.norm() * r.powi(i as i32))
+        .sum();
+    let tolerance = MULTIPLE_SPREAD * f64::EPSILON * cond;
+    shifted[0].norm() <= tolerance && spread <= (tolerance / shifted[m].norm()).powf(1.0 / m as f64)
+}
+
+fn split(coeffs: &[Complex<f64>], roots: &[Cluster], eps: f64) -> Vec<Cluster> {
+    groups(roots, eps)
+        .into_iter()
+        .flat_map(|group| {
+            let c = merge(roots, &group);
+            let roots: Vec<_> = group.iter().map(|&k| roots[k]).collect();
+            if roots.len() == 1 || is_multiple(coeffs, &roots, c.center, eps) {
+                vec![Cluster {
+                    tag: c.count as u32,
+                    ..c
+                }]
+            } else {
+                split(coeffs, &roots, eps / SPLIT_FACTOR)
+            }
+        })
+        .collect()
+}
+
+pub fn poly_roots(coeffs: &[Complex<f64>], eps: f64) -> Vec<Cluster> {
+    let roots = aberth(coeffs);
+    let scale = roots.iter().map(|x| x.norm()).fold(0.0, f64::max);
+    let roots: Vec<_> = roots
+        .into_iter()
+        .filter(|x| x.re.is_finite() && x.im.is_finite())
+        .map(|x| Cluster::new(x, 1))
+        .collect();
+    let mut res = split(coeffs, &roots, eps.max(CLUSTER_PRECISION * (1.0 + scale)));
+    res.sort_by(|a, b| {
+        a.center
+            .re
+            .total_cmp(&b.center.re)
+            .then(a.center.im.total_cmp(&b.center.im))
+    });
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_roots(roots: &[(f64, u32)]) -> Vec<Complex<f64>> {
+        let mut p = vec![Complex::new(1.0, 0.0)];
+        for &(x, m) in roots {
+            for _ in 0..m {
+                let mut q = vec![Complex::new(0.0, 0.0); p.len() + 1];
+                for (i, a) in p.iter().enumerate() {
+                    q[i + 1] += a;
+                    q[i] -= a * x;
+                }
+                p = q;
+            }
+        }
+        p
+    }
+
+    fn check(coeffs: &[Complex<f64>], expected: &[(f64, u32)]) {
+        let roots = poly_roots(coeffs, 1e-8);
+        assert_eq!(
+            roots.len(),
+            expected.len(),
+            "{:?}",
+            roots.iter().map(|c| c.center).collect::<Vec<_>>()
+        );
+        for (root, &(x, m)) in roots.iter().zip(expected) {
+            assert_eq!(root.tag, m, "{}", x);
+            assert!((root.center - x).norm() < 1e-3, "{} != {}", root.center, x);
+        }
+    }
+
+    #[test]
+    fn multiple_roots_at_origin() {
+        check(&from_roots(&[(0.0, 2)]), &[(0.0, 2)]);
+        check(&from_roots(&[(0.0, 5)]), &[(0.0, 5)]);
+        check(&from_roots(&[(0.0, 3), (1.0, 1)]), &[(0.0, 3), (1.0, 1)]);
+    }
+
+    #[test]
+    fn multiple_roots_with_complex_conjugates() {
+        let mut p = from_roots(&[(0.0, 2)]);
+        p[2] = Complex::new(1.0, 0.0);
+        p.push(Complex::new(0.0, 0.0));
+        p.push(Complex::new(1.0, 0.0));
+        let mut roots = poly_roots(&p, 1e-8);
+        roots.sort_by(|a, b| a.center.im.total_cmp(&b.center.im));
+        let tags: Vec<_> = roots.iter().map(|c| c.tag).collect();
+        assert_eq!(tags, [1, 2, 1]);
+        assert!(roots[1].center.norm() < 1e-3);
+    }
+
+    #[test]
+    fn multiple_roots_away_from_origin() {
+        check(&from_roots(&[(-2.0, 1), (1.0, 3)]), &[(-2.0, 1), (1.0, 3)]);
+        check(&from_roots(&[(1.0, 8)]), &[(1.0, 8)]);
+    }
+
+    #[test]
+    fn close_distinct_roots() {
+        check(
+            &from_roots(&[(1.0, 1), (1.001, 1)]),
+            &[(1.0, 1), (1.001, 1)],
+        );
+        check(
+            &from_roots(&[(1.0, 1), (1.0005, 1)]),
+            &[(1.0, 1), (1.0005, 1)],
+        );
+        check(
+            &from_roots(&[(-1e-4, 1), (1e-4, 1)]),
+            &[(-1e-4, 1), (1e-4, 1)],
+        );
+    }
+
+    #[test]
+    fn multiple_root_next_to_simple_root() {
+        check(
+            &from_roots(&[(1.0, 2), (1.0005, 1)]),
+            &[(1.0, 2), (1.0005, 1)],
+        );
+    }
+
+    #[test]
+    fn roots_of_unity_stay_distinct() {
+        let mut p = vec![Complex::new(0.0, 0.0); 301];
+        p[0] = Complex::new(-1.0, 0.0);
+        p[300] = Complex::new(1.0, 0.0);
+        let roots = poly_roots(&p, 1e-8);
+        assert_eq!(roots.len(), 300);
+        assert!(roots.iter().all(|c| c.tag == 1));
+    }
+}
@@ -0,0 +1,94 @@
+use num_complex::Complex;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    Plane,
+    Exp,
+    Sphere,
+}
+
+impl Projection {
+    pub const NAMES: &'static [&'static str] = &["plane", "exp", "sphere"];
+
+    pub fn from_name(name: &str) -> Option<Projection> {
+        match name.trim() {
+            "plane" => Some(Projection::Plane),
+            "exp" => Some(Projection::Exp),
+            "sphere" => Some(Projection::Sphere),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, w: Complex<f64>) -> Option<Complex<f64>> {
+        match self {
+            Projection::Plane => Some(w),
+            Projection::Exp => Some(w.exp()),
+            Projection::Sphere => {
+                let r = w.norm_sqr();
+                if r > 1.0 {
+                    return None;
+                }
+                Some(w / (1.0 + (1.0 - r).sqrt()))
+            }
+        }
+    }
+}
+
+pub fn validate_projection(projection: String) -> Result<(), String> {
+    match Projection::from_name(&projection) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "Неизвестная проекция, допустимые значения: {}",
+            Projection::NAMES.join(", ")
+        )),
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Mobius {
+    a: Complex<f64>,
+    b: Complex<f64>,
+    c: Complex<f64>,
+    d: Complex<f64>,
+}
+
+impl Mobius {
+    pub fn apply(&self, z: Complex<f64>) -> Complex<f64> {
+        (self.a * z + self.b) / (self.c * z + self.d)
+    }
+}
+
+fn parse_complex(x: &str) -> Option<Complex<f64>> {
+    let (re, im) = x.split_once(',')?;
+    let x: Complex<f64> = Complex::new(re.trim().parse().ok()?, im.trim().parse().ok()?);
+    if x.re.is_finite() && x.im.is_finite() {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+fn parse_mobius(mobius: &str) -> Result<Mobius, String> {
+    let coeffs = mobius
+        .split(';')
+        .map(parse_complex)
+        .collect::<Option<Vec<_>>>()
+        .ok_or("Коэффициенты преобразования Мёбиуса должны иметь вид Re, Im")?;
+    let [a, b, c, d] = coeffs[..] else {
+        return Err(
+            "Преобразование Мёбиуса задаётся четырьмя коэффициентами a; b; c; d".to_string(),
+        );
+    };
+    if (a * d - b * c).norm_sqr() == 0.0 {
+        return Err("Преобразование Мёбиуса вырождено: ad - bc = 0".to_string());
+    }
+    Ok(Mobius { a, b, c, d })
+}
+
+pub fn validate_mobius(mobius: String) -> Result<(), String> {
+    parse_mobius(&mobius).map(|_| ())
+}
+
+pub fn get_mobius(mobius: &str) -> Mobius {
+    parse_mobius(mobius).unwrap()
+}
@@ -0,0 +1,165 @@
+use num_complex::Complex;
+use num_traits::Num;
+
+use crate::dd::DD;
+use crate::{F32_FUNC, FDD_FUNC, F_FUNC, G32_FUNC, GDD_FUNC, G_FUNC};
+
+pub trait Real: Num + Copy + PartialOrd + Send + Sync {
+    const EPSILON: f64;
+
+    fn from_f64(x: f64) -> Self;
+    fn from_dd(x: DD) -> Self;
+    fn to_f64(self) -> f64;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn is_finite(self) -> bool;
+
+    fn norm(x: Complex<Self>) -> f64 {
+        let (re, im) = (x.re.abs(), x.im.abs());
+        let (max, min) = if re > im { (re, im) } else { (im, re) };
+        if max == Self::zero() {
+            0.0
+        } else {
+            let r = min / max;
+            (max * (Self::one() + r * r).sqrt()).to_f64()
+        }
+    }
+
+    fn f(x: Complex<Self>) -> Complex<Self>;
+    fn g(x: Complex<Self>) -> Complex<Self>;
+}
+
+pub fn tolerance<T: Real>(eps: f64) -> f64 {
+    eps.powf(T::EPSILON.ln() / f64::EPSILON.ln())
+}
+
+pub fn is_finite<T: Real>(x: Complex<T>) -> bool {
+    x.re.is_finite() && x.im.is_finite()
+}
+
+pub fn to_f64<T: Real>(x: Complex<T>) -> Complex<f64> {
+    Complex::new(x.re.to_f64(), x.im.to_f64())
+}
+
+impl Real for f32 {
+    const EPSILON: f64 = f32::EPSILON as f64;
+
+    fn from_f64(x: f64) -> f32 {
+        x as f32
+    }
+
+    fn from_dd(x: DD) -> f32 {
+        x.to_f64() as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn sqrt(self) -> f32 {
+        f32::sqrt(self)
+    }
+
+    fn abs(self) -> f32 {
+        f32::abs(self)
+    }
+
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+
+    fn norm(x: Complex<f32>) -> f64 {
+        x.norm() as f64
+    }
+
+    fn f(x: Complex<f32>) -> Complex<f32> {
+        unsafe { F32_FUNC.unwrap()(x) }
+    }
+
+    fn g(x: Complex<f32>) -> Complex<f32> {
+        unsafe { G32_FUNC.unwrap()(x) }
+    }
+}
+
+impl Real for f64 {
+    const EPSILON: f64 = f64::EPSILON;
+
+    fn from_f64(x: f64) -> f64 {
+        x
+    }
+
+    fn from_dd(x: DD) -> f64 {
+        x.to_f64()
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn sqrt(self) -> f64 {
+        f64::sqrt(self)
+    }
+
+    fn abs(self) -> f64 {
+        f64::abs(self)
+    }
+
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+
+    fn norm(x: Complex<f64>) -> f64 {
+        x.norm()
+    }
+
+    fn f(x: Complex<f64>) -> Complex<f64> {
+        unsafe { F_FUNC.unwrap()(x) }
+    }
+
+    fn g(x: Complex<f64>) -> Complex<f64> {
+        unsafe { G_FUNC.unwrap()(x) }
+    }
+}
+
+impl Real for DD {
+    const EPSILON: f64 = 4.93038065763132e-32;
+
+    fn from_f64(x: f64) -> DD {
+        DD::from(x)
+    }
+
+    fn from_dd(x: DD) -> DD {
+        x
+    }
+
+    fn to_f64(self) -> f64 {
+        DD::to_f64(self)
+    }
+
+    fn sqrt(self) -> DD {
+        DD::sqrt(self)
+    }
+
+    fn abs(self) -> DD {
+        DD::abs(self)
+    }
+
+    fn is_finite(self) -> bool {
+        self.hi.is_finite() && self.lo.is_finite()
+    }
+
+    fn f(x: Complex<DD>) -> Complex<DD> {
+        eval_dd(unsafe { FDD_FUNC.unwrap() }, x)
+    }
+
+    fn g(x: Complex<DD>) -> Complex<DD> {
+        eval_dd(unsafe { GDD_FUNC.unwrap() }, x)
+    }
+}
+
+fn eval_dd(func: crate::CFuncDD, x: Complex<DD>) -> Complex<DD> {
+    let x = [x.re.hi, x.re.lo, x.im.hi, x.im.lo];
+    let mut r = [0.0; 4];
+    unsafe { func(x.as_ptr(), r.as_mut_ptr()) };
+    Complex::new(DD::new(r[0], r[1]), DD::new(r[2], r[3]))
+}
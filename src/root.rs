@@ -0,0 +1,305 @@
+use num_complex::Complex;
+
+use crate::antialias::Antialias;
+use crate::cluster::RootOrder;
+use crate::real::*;
+
+const SECANT_SHIFT: f64 = 1e-4;
+const CYCLE_PRECISION: f64 = 1e-3;
+const MAX_MULTIPLICITY: f64 = 16.0;
+const ARMIJO: f64 = 1e-4;
+const MAX_BACKTRACK: u32 = 30;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Newton,
+    Secant,
+    Steffensen,
+    Damped,
+}
+
+impl Method {
+    pub const NAMES: &'static [&'static str] = &["newton", "secant", "steffensen", "damped"];
+
+    pub fn from_name(name: &str) -> Option<Method> {
+        match name.trim() {
+            "newton" => Some(Method::Newton),
+            "secant" => Some(Method::Secant),
+            "steffensen" => Some(Method::Steffensen),
+            "damped" => Some(Method::Damped),
+            _ => None,
+        }
+    }
+
+    pub fn needs_diff(self) -> bool {
+        matches!(self, Method::Newton | Method::Damped)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Stop {
+    pub residual: bool,
+    pub step: bool,
+    pub relstep: bool,
+}
+
+impl Stop {
+    pub const NAMES: &'static [&'static str] = &["residual", "step", "relstep"];
+
+    pub fn from_names(names: &str) -> Option<Stop> {
+        let mut stop = Stop {
+            residual: false,
+            step: false,
+            relstep: false,
+        };
+        for name in names.split('+') {
+            match name.trim() {
+                "residual" => stop.residual = true,
+                "step" => stop.step = true,
+                "relstep" => stop.relstep = true,
+                _ => return None,
+            }
+        }
+        Some(stop)
+    }
+
+    fn converged(self, fx: f64, dx: f64, x: f64, eps: f64) -> bool {
+        (!self.residual || fx < eps) && (!self.step || dx < eps) && (!self.relstep || dx < eps * x)
+    }
+}
+
+impl Default for Stop {
+    fn default() -> Stop {
+        Stop {
+            residual: true,
+            step: false,
+            relstep: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Params {
+    pub method: Method,
+    pub stop: Stop,
+    pub precision: f64,
+    pub root_precision: f64,
+    pub escape: Option<f64>,
+    pub cycles: bool,
+    pub multiplicity: bool,
+    pub order: RootOrder,
+    pub iter: u32,
+    pub contrast: f64,
+    pub aa: Option<Antialias>,
+    pub smooth: bool,
+}
+
+#[derive(Clone, Copy)]
+pub enum Outcome {
+    Converged {
+        root: Complex<f64>,
+        iters: u32,
+        smooth: f64,
+        multiplicity: u32,
+    },
+    Cycle {
+        point: Complex<f64>,
+        period: u32,
+    },
+    Escaped,
+    DerivativeVanished,
+    NaN,
+    MaxIter,
+    Outside,
+}
+
+impl Outcome {
+    pub fn is_attracted(&self) -> bool {
+        matches!(self, Outcome::Converged { .. } | Outcome::Cycle { .. })
+    }
+}
+
+pub fn validate_stop(stop: String) -> Result<(), String> {
+    match Stop::from_names(&stop) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "Неизвестный критерий остановки, допустимые значения: {} (можно объединять через +)",
+            Stop::NAMES.join(", ")
+        )),
+    }
+}
+
+pub fn validate_method(method: String) -> Result<(), String> {
+    match Method::from_name(&method) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "Неизвестный метод, допустимые значения: {}",
+            Method::NAMES.join(", ")
+        )),
+    }
+}
+
+pub fn find_root<T: Real>(x: Complex<T>, params: &Params) -> Outcome {
+    match params.method {
+        Method::Newton | Method::Damped => iterate(x, params, |t| (T::f(t), T::g(t))),
+        Method::Secant => {
+            let mut prev = x;
+            let mut fprev = T::f(prev);
+            let shift = T::from_f64(SECANT_SHIFT * (1.0 + T::norm(x)));
+            iterate(x + shift, params, |t| {
+                let fc = T::f(t);
+                let gc = (fc - fprev) / (t - prev);
+                prev = t;
+                fprev = fc;
+                (fc, gc)
+            })
+        }
+        Method::Steffensen => iterate(x, params, |t| {
+            let fc = T::f(t);
+            (fc, (T::f(t + fc) - fc) / fc)
+        }),
+    }
+}
+
+fn iterate<T: Real>(
+    mut x: Complex<T>,
+    params: &Params,
+    mut slope: impl FnMut(Complex<T>) -> (Complex<T>, Complex<T>),
+) -> Outcome {
+    let (mut tortoise, mut power, mut lam) = (x, 1u32, 0u32);
+    let cycle_precision = params.root_precision * CYCLE_PRECISION;
+    let (mut dprev, mut m, mut multiplicity) = (None, 1.0, 1);
+    let mut rprev = None;
+
+    for iters in 0..params.iter {
+        let t = x;
+        let (fc, gc) = slope(t);
+        let d = fc / gc;
+        let residual = T::norm(fc);
+
+        if let Some(dprev) = dprev {
+            let rho = to_f64(d / dprev);
+            if rho.norm() < 1.0 {
+                let estimate = (m / (1.0 - rho)).re.round().clamp(1.0, MAX_MULTIPLICITY) as u32;
+                if params.multiplicity && estimate == multiplicity {
+                    m = multiplicity as f64;
+                }
+                multiplicity = estimate;
+            }
+        }
+        dprev = Some(d);
+        x = advance(t, fc, d * T::from_f64(m), params);
+
+        let vanished = gc.norm_sqr() == T::zero();
+        if vanished || !is_finite(x) {
+            return if residual < params.precision {
+                Outcome::Converged {
+                    root: to_f64(t),
+                    iters,
+                    smooth: smooth_iters(iters, residual, rprev, params.precision),
+                    multiplicity,
+                }
+            } else if vanished {
+                Outcome::DerivativeVanished
+            } else {
+                Outcome::NaN
+            };
+        }
+        if params
+            .stop
+            .converged(residual, T::norm(x - t), T::norm(x), params.precision)
+        {
+            return Outcome::Converged {
+                root: to_f64(x),
+                iters,
+                smooth: smooth_iters(iters, residual, rprev, params.precision),
+                multiplicity,
+            };
+        }
+        if params.escape.is_some_and(|r| T::norm(x) > r) {
+            return Outcome::Escaped;
+        }
+        rprev = Some(residual);
+
+        if params.cycles {
+            lam += 1;
+            if lam > 1
+                && T::norm(x - tortoise) < cycle_precision
+                && T::norm(x - t) >= cycle_precision
+            {
+                return Outcome::Cycle {
+                    point: canonical_point(x, lam, m, params, &mut slope),
+                    period: lam,
+                };
+            }
+            if lam == power {
+                tortoise = x;
+                power = power.saturating_mul(2);
+                lam = 0;
+            }
+        }
+    }
+    Outcome::MaxIter
+}
+
+fn smooth_iters(iters: u32, residual: f64, prev: Option<f64>, eps: f64) -> f64 {
+    let Some(prev) = prev else {
+        return iters as f64;
+    };
+    let (l, lp, le) = (residual.ln(), prev.ln(), eps.ln());
+    let frac = if lp < 0.0 && l < lp {
+        (le / lp).ln() / (l / lp).ln()
+    } else {
+        (lp - le) / (lp - l)
+    };
+    iters as f64 - 1.0
+        + if frac.is_finite() {
+            frac.clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+}
+
+fn advance<T: Real>(
+    x: Complex<T>,
+    fx: Complex<T>,
+    step: Complex<T>,
+    params: &Params,
+) -> Complex<T> {
+    if params.method == Method::Damped {
+        x - step * T::from_f64(line_search(x, fx, step))
+    } else {
+        x - step
+    }
+}
+
+fn line_search<T: Real>(x: Complex<T>, fx: Complex<T>, step: Complex<T>) -> f64 {
+    let norm = T::norm(fx);
+    let mut lambda = 1.0;
+    for _ in 0..MAX_BACKTRACK {
+        if T::norm(T::f(x - step * T::from_f64(lambda))) <= (1.0 - ARMIJO * lambda) * norm {
+            break;
+        }
+        lambda *= 0.5;
+    }
+    lambda
+}
+
+fn canonical_point<T: Real>(
+    mut x: Complex<T>,
+    period: u32,
+    m: f64,
+    params: &Params,
+    slope: &mut impl FnMut(Complex<T>) -> (Complex<T>, Complex<T>),
+) -> Complex<f64> {
+    let mut point = to_f64(x);
+    for _ in 1..period {
+        let (fc, gc) = slope(x);
+        x = advance(x, fc, fc / gc * T::from_f64(m), params);
+        let p = to_f64(x);
+        if (p.re, p.im) < (point.re, point.im) {
+            point = p;
+        }
+    }
+    point
+}
@@ -0,0 +1,185 @@
+use num_complex::Complex;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::Color;
+
+pub struct Basin {
+    pub center: Complex<f64>,
+    pub tag: u32,
+    pub color: Option<Color>,
+    pub pixels: u64,
+    pub iters: u64,
+    pub max_iter: u32,
+}
+
+impl Basin {
+    pub fn new(center: Complex<f64>, tag: u32, color: Option<Color>) -> Basin {
+        Basin {
+            center,
+            tag,
+            color,
+            pixels: 0,
+            iters: 0,
+            max_iter: 0,
+        }
+    }
+
+    fn mean_iter(&self) -> f64 {
+        if self.pixels == 0 {
+            0.0
+        } else {
+            self.iters as f64 / self.pixels as f64
+        }
+    }
+
+    fn color(&self) -> Option<String> {
+        self.color
+            .map(|Color(r, g, b)| format!("#{}", hex::encode([r, g, b])))
+    }
+}
+
+#[derive(Default)]
+pub struct Stats {
+    pub width: u32,
+    pub height: u32,
+    pub roots: Vec<Basin>,
+    pub cycles: Vec<Basin>,
+    pub unmatched: u64,
+    pub non_converged: u64,
+    pub escaped: u64,
+    pub cycle: u64,
+    pub vanished: u64,
+    pub nan: u64,
+    pub outside: u64,
+}
+
+pub fn validate_stats(path: String) -> Result<(), String> {
+    if path.ends_with(".json") || path.ends_with(".csv") {
+        Ok(())
+    } else {
+        Err("Файл статистики должен иметь расширение json или csv".to_string())
+    }
+}
+
+impl Stats {
+    fn fraction(&self, pixels: u64) -> f64 {
+        pixels as f64 / (self.width as f64 * self.height as f64)
+    }
+
+    fn outcomes(&self) -> [(&'static str, u64); 7] {
+        [
+            ("unmatched", self.unmatched),
+            ("non_converged", self.non_converged),
+            ("escaped", self.escaped),
+            ("cycle", self.cycle),
+            ("vanished", self.vanished),
+            ("nan", self.nan),
+            ("outside", self.outside),
+        ]
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), std::io::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        if path.ends_with(".csv") {
+            self.write_csv(&mut file)
+        } else {
+            self.write_json(&mut file)
+        }
+    }
+
+    fn write_csv(&self, w: &mut impl Write) -> Result<(), std::io::Error> {
+        writeln!(
+            w,
+            "type,re,im,multiplicity,period,color,pixels,fraction,mean_iter,max_iter"
+        )?;
+        for root in &self.roots {
+            writeln!(
+                w,
+                "root,{},{},{},,{},{},{},{},{}",
+                root.center.re,
+                root.center.im,
+                root.tag,
+                root.color().unwrap_or_default(),
+                root.pixels,
+                self.fraction(root.pixels),
+                root.mean_iter(),
+                root.max_iter
+            )?;
+        }
+        for cycle in &self.cycles {
+            writeln!(
+                w,
+                "cycle,{},{},,{},{},{},{},,",
+                cycle.center.re,
+                cycle.center.im,
+                cycle.tag,
+                cycle.color().unwrap_or_default(),
+                cycle.pixels,
+                self.fraction(cycle.pixels)
+            )?;
+        }
+        for (name, pixels) in self.outcomes() {
+            writeln!(w, "{},,,,,,{},{},,", name, pixels, self.fraction(pixels))?;
+        }
+        Ok(())
+    }
+
+    fn write_json(&self, w: &mut impl Write) -> Result<(), std::io::Error> {
+        let color = |basin: &Basin| {
+            basin
+                .color()
+                .map_or("null".to_string(), |c| format!("\"{}\"", c))
+        };
+
+        writeln!(w, "{{")?;
+        writeln!(w, "  \"width\": {},", self.width)?;
+        writeln!(w, "  \"height\": {},", self.height)?;
+        writeln!(w, "  \"roots\": [")?;
+        for (k, root) in self.roots.iter().enumerate() {
+            writeln!(
+                w,
+                "    {{\"re\": {}, \"im\": {}, \"multiplicity\": {}, \"color\": {}, \"pixels\": {}, \"fraction\": {}, \"mean_iter\": {}, \"max_iter\": {}}}{}",
+                root.center.re,
+                root.center.im,
+                root.tag,
+                color(root),
+                root.pixels,
+                self.fraction(root.pixels),
+                root.mean_iter(),
+                root.max_iter,
+                if k + 1 < self.roots.len() { "," } else { "" }
+            )?;
+        }
+        writeln!(w, "  ],")?;
+        writeln!(w, "  \"cycles\": [")?;
+        for (k, cycle) in self.cycles.iter().enumerate() {
+            writeln!(
+                w,
+                "    {{\"re\": {}, \"im\": {}, \"period\": {}, \"color\": {}, \"pixels\": {}, \"fraction\": {}}}{}",
+                cycle.center.re,
+                cycle.center.im,
+                cycle.tag,
+                color(cycle),
+                cycle.pixels,
+                self.fraction(cycle.pixels),
+                if k + 1 < self.cycles.len() { "," } else { "" }
+            )?;
+        }
+        writeln!(w, "  ],")?;
+        writeln!(w, "  \"outcomes\": {{")?;
+        let outcomes = self.outcomes();
+        for (k, (name, pixels)) in outcomes.iter().enumerate() {
+            writeln!(
+                w,
+                "    \"{}\": {{\"pixels\": {}, \"fraction\": {}}}{}",
+                name,
+                pixels,
+                self.fraction(*pixels),
+                if k + 1 < outcomes.len() { "," } else { "" }
+            )?;
+        }
+        writeln!(w, "  }}")?;
+        writeln!(w, "}}")
+    }
+}